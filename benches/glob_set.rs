@@ -0,0 +1,114 @@
+//! Compare checking globs one at a time with a compiled [`GlobSet`], on
+//! something shaped like rustc's source tarball: hundreds of
+//! `Files-Excluded` globs, mostly for vendored crates, and a couple of
+//! hundred thousand paths.
+//!
+//! Run with `cargo bench --bench glob_set`.
+
+use std::{
+  hint::black_box,
+  str::FromStr,
+  time::{Duration, Instant},
+};
+
+use deb_strip_copyright::glob::{Glob, set::GlobSet};
+use fastrand::Rng;
+
+const CRATES: usize = 1500;
+const FILES_PER_CRATE: usize = 150;
+const EXCLUDED_CRATES: usize = 400;
+
+fn crate_dir(idx: usize) -> String {
+  format!("vendor/crate{}-0.{}.{}", idx, idx % 7, idx % 13)
+}
+
+fn globs(rng: &mut Rng) -> Vec<Glob> {
+  let mut globs: Vec<String> = (0..EXCLUDED_CRATES)
+    .map(|_| crate_dir(rng.usize(0..CRATES)))
+    .collect();
+  globs.extend((0..40).map(|idx| format!("vendor/windows{}-*", idx)));
+  globs.extend(
+    [
+      "clang", "lldb", "mlir", "flang", "openmp", "polly", "libc", "bolt",
+    ]
+    .iter()
+    .map(|dir| format!("src/llvm-project/{}", dir)),
+  );
+  globs.extend(
+    [
+      "*.min.js", "*.pdf", "*.woff", "*.dll", "*.exe", "*.a", "*.lib",
+    ]
+    .map(str::to_owned),
+  );
+  globs.extend(
+    ["vendor/*/tests", "vendor/*/benches", ".gitmodules"].map(str::to_owned),
+  );
+  globs
+    .iter()
+    .map(|glob| Glob::from_str(glob).unwrap())
+    .collect()
+}
+
+fn paths(rng: &mut Rng) -> Vec<String> {
+  let extensions = ["rs", "rs", "rs", "toml", "md", "json", "min.js", "a"];
+  let mut paths = Vec::new();
+  for idx in 0..CRATES {
+    let dir = crate_dir(idx);
+    for file in 0..FILES_PER_CRATE {
+      let sub = rng
+        .choice(["src", "src/imp", "tests", "benches", "examples"])
+        .unwrap();
+      let ext = rng.choice(extensions).unwrap();
+      paths.push(format!("{}/{}/file{}.{}", dir, sub, file, ext));
+    }
+  }
+  for idx in 0..20_000 {
+    let dir = rng
+      .choice(["clang", "llvm", "lldb", "compiler-rt"])
+      .unwrap();
+    paths.push(format!("src/llvm-project/{}/lib/file{}.cpp", dir, idx));
+  }
+  paths
+}
+
+fn time<F: FnMut() -> usize>(name: &str, mut f: F) -> (Duration, usize) {
+  // Warm up, then take the best of a few runs
+  let count = f();
+  let best = (0..3)
+    .map(|_| {
+      let start = Instant::now();
+      black_box(f());
+      start.elapsed()
+    })
+    .min()
+    .unwrap();
+  println!("{:>8}: {:>10.2?}, {} paths excluded", name, best, count);
+  (best, count)
+}
+
+fn main() {
+  let mut rng = Rng::with_seed(0xb3);
+  let globs = globs(&mut rng);
+  let paths = paths(&mut rng);
+  println!("{} globs, {} paths", globs.len(), paths.len());
+
+  let (linear, linear_count) = time("linear", || {
+    paths
+      .iter()
+      .filter(|path| globs.iter().any(|glob| glob.matches(path)))
+      .count()
+  });
+
+  let start = Instant::now();
+  let set = GlobSet::new(globs.clone());
+  println!("compiling the set took {:.2?}", start.elapsed());
+  let (compiled, set_count) = time("set", || {
+    paths.iter().filter(|path| set.is_match(path)).count()
+  });
+
+  assert_eq!(linear_count, set_count, "the set disagrees with the globs");
+  println!(
+    "{:.1}x faster",
+    linear.as_secs_f64() / compiled.as_secs_f64()
+  );
+}
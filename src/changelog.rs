@@ -0,0 +1,477 @@
+//! Parse `debian/changelog`.
+//!
+//! The format is in the "Debian changelog" section of Debian policy:
+//! https://www.debian.org/doc/debian-policy/ch-source#debian-changelog-debian-changelog
+//!
+//! Each entry looks like this, newest first:
+//!
+//! ```text
+//! package (version) distribution(s); urgency=urgency
+//!
+//!   * change details
+//!     more change details
+//!
+//!  -- maintainer name <email address>  Mon, 06 Oct 2025 12:11:39 -0500
+//! ```
+
+use std::{path::Path, str::FromStr};
+
+use eyre::{Context, bail, eyre};
+use log::{info, warn};
+
+use crate::{
+  span::{self, Line},
+  version::Version,
+};
+
+/// Where the changelog is, relative to the source package.
+pub const DEFAULT_PATH: &str = "./debian/changelog";
+
+/// A whole changelog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changelog {
+  /// The entries, newest first, like in the file.
+  pub entries: Vec<ChangelogEntry>,
+}
+
+/// One entry, from the header line to the ` -- ` trailer line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogEntry {
+  /// The source package name.
+  pub package: String,
+  pub version: Version,
+  pub distributions: Vec<String>,
+  /// The `urgency` from the header, if it has one.
+  pub urgency: Option<String>,
+  /// Every other `key=value` pair in the header, in order.
+  pub extra: Vec<(String, String)>,
+  /// The lines between the header and the trailer, indentation and all.
+  /// Blank lines at the start and end are taken off.
+  pub changes: Vec<String>,
+  /// Who made the entry, like `Name <email@example.com>`.
+  pub maintainer: String,
+  pub date: ChangelogDate,
+}
+
+/// An RFC 2822 date, like `Mon, 06 Oct 2025 12:11:39 -0500`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangelogDate {
+  pub year: i32,
+  /// From 1 to 12.
+  pub month: u32,
+  pub day: u32,
+  pub hour: u32,
+  pub minute: u32,
+  pub second: u32,
+  /// Offset from UTC, in minutes. `-0500` is `-300`.
+  pub offset: i32,
+}
+
+const MONTHS: [&str; 12] = [
+  "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov",
+  "Dec",
+];
+
+/// Starting from Thursday, because 1970-01-01 was one.
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+/// Lines that end the changelog proper. Anything after them is old
+/// free-form history, or editor settings.
+const END_MARKERS: &[&str] = &["Old Changelog:", "Local variables:"];
+
+impl Changelog {
+  /// Read and parse the changelog at `path`.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).wrap_err_with(|| {
+      eyre!("could not read changelog at {}", path.display())
+    })?;
+
+    Changelog::from_str(&source).wrap_err(eyre!("could not parse changelog"))
+  }
+
+  /// The newest entry.
+  pub fn latest(&self) -> Option<&ChangelogEntry> {
+    self.entries.first()
+  }
+}
+
+impl ChangelogEntry {
+  /// The name of the orig tarball for this entry's version, like
+  /// `foo_1.0+dfsg.orig.tar.xz`.
+  pub fn orig_tarball_name(&self, ext: &str) -> String {
+    self.version.orig_tarball_name(&self.package, ext)
+  }
+}
+
+impl ChangelogDate {
+  /// Seconds since the unix epoch.
+  pub fn timestamp(&self) -> i64 {
+    let local = days_from_civil(self.year, self.month, self.day) * 86400
+      + self.hour as i64 * 3600
+      + self.minute as i64 * 60
+      + self.second as i64;
+    local - self.offset as i64 * 60
+  }
+
+  /// The day of the week, like `Mon`.
+  pub fn weekday(&self) -> &'static str {
+    let days = days_from_civil(self.year, self.month, self.day);
+    WEEKDAYS[days.rem_euclid(7) as usize]
+  }
+}
+
+/// Howard Hinnant's days_from_civil.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+  let year = year as i64 - if month <= 2 { 1 } else { 0 };
+  let era = year.div_euclid(400);
+  let yoe = year.rem_euclid(400);
+  let mp = (month as i64 + 9) % 12;
+  let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+  era * 146097 + doe - 719468
+}
+
+/// How many days `month` has in `year`.
+fn days_in_month(year: i32, month: u32) -> u32 {
+  match month {
+    2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+impl FromStr for ChangelogDate {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut words = s.split_whitespace().peekable();
+    // The day of the week is optional
+    let weekday = words
+      .next_if(|word| word.ends_with(','))
+      .map(|word| word.trim_end_matches(','));
+
+    let mut next = |what: &str| {
+      words
+        .next()
+        .ok_or_else(|| eyre!("date {:?} has no {}", s, what))
+    };
+    let number = |text: &str, what: &str| {
+      text
+        .parse::<u32>()
+        .map_err(|_| eyre!("bad {} {:?} in date {:?}", what, text, s))
+    };
+
+    let day = number(next("day")?, "day")?;
+    let month_name = next("month")?;
+    let month = MONTHS
+      .iter()
+      .position(|month| *month == month_name)
+      .ok_or_else(|| eyre!("bad month {:?} in date {:?}", month_name, s))?
+      as u32
+      + 1;
+    let year = next("year")?;
+    let year = year
+      .parse::<i32>()
+      .map_err(|_| eyre!("bad year {:?} in date {:?}", year, s))?;
+    let time = next("time")?;
+    let mut time_parts = time.split(':');
+    let mut time_part = |what: &str| {
+      let part = time_parts
+        .next()
+        .ok_or_else(|| eyre!("bad time {:?} in date {:?}", time, s))?;
+      if part.len() != 2 {
+        bail!("bad {} {:?} in date {:?}", what, part, s);
+      }
+      number(part, what)
+    };
+    let hour = time_part("hour")?;
+    let minute = time_part("minute")?;
+    let second = time_part("second")?;
+    let zone = next("time zone")?;
+    let offset = match zone.split_at_checked(1) {
+      Some((sign @ ("+" | "-"), digits))
+        if digits.len() == 4 && digits.bytes().all(|b| b.is_ascii_digit()) =>
+      {
+        let minutes =
+          (digits[..2].parse::<i32>()? * 60) + digits[2..].parse::<i32>()?;
+        if sign == "-" { -minutes } else { minutes }
+      }
+      _ => bail!("bad time zone {:?} in date {:?}", zone, s),
+    };
+    if let Some(extra) = words.next() {
+      bail!("unexpected {:?} at the end of date {:?}", extra, s);
+    }
+
+    if !(1..=days_in_month(year, month)).contains(&day)
+      || hour > 23
+      || minute > 59
+      || second > 60
+    {
+      bail!("date {:?} is out of range", s);
+    }
+    let date = ChangelogDate {
+      year,
+      month,
+      day,
+      hour,
+      minute,
+      second,
+      offset,
+    };
+    if let Some(weekday) = weekday
+      && weekday != date.weekday()
+    {
+      warn!(
+        "date {:?} says {}, but that day was a {}",
+        s,
+        weekday,
+        date.weekday()
+      );
+    }
+    Ok(date)
+  }
+}
+
+/// Writes the RFC 2822 form, like `Mon, 06 Oct 2025 12:11:39 -0500`.
+impl std::fmt::Display for ChangelogDate {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let sign = if self.offset < 0 { '-' } else { '+' };
+    let offset = self.offset.abs();
+    write!(
+      f,
+      "{}, {:02} {} {:04} {:02}:{:02}:{:02} {}{:02}{:02}",
+      self.weekday(),
+      self.day,
+      MONTHS[self.month as usize - 1],
+      self.year,
+      self.hour,
+      self.minute,
+      self.second,
+      sign,
+      offset / 60,
+      offset % 60
+    )
+  }
+}
+
+// Parsing.
+
+impl FromStr for Changelog {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut lines = span::lines(s).peekable();
+
+    let mut entries = Vec::new();
+    loop {
+      // Blank lines between entries
+      while lines.next_if(|line| line.text.trim().is_empty()).is_some() {}
+      let Some(header) = lines.next() else {
+        break;
+      };
+      if END_MARKERS
+        .iter()
+        .any(|marker| header.text.starts_with(marker))
+      {
+        break;
+      }
+
+      let mut entry = parse_header(s, &header)?;
+
+      let mut trailer = None;
+      for line in lines.by_ref() {
+        if line.text.starts_with(" -- ") {
+          trailer = Some(line);
+          break;
+        }
+        if !line.text.trim().is_empty() && !line.text.starts_with([' ', '\t']) {
+          return Err(span::error(
+            s,
+            line.span(),
+            "change lines must start with whitespace",
+          ));
+        }
+        entry.changes.push(line.text.trim_end().to_owned());
+      }
+      let trailer = trailer.ok_or_else(|| {
+        span::error(s, header.span(), "entry has no ` -- ` trailer line")
+      })?;
+      parse_trailer(s, &trailer, &mut entry)?;
+
+      let blank = |line: &String| line.is_empty();
+      let start = entry.changes.iter().position(|line| !blank(line));
+      let end = entry.changes.iter().rposition(|line| !blank(line));
+      entry.changes = match (start, end) {
+        (Some(start), Some(end)) => entry.changes[start..=end].to_vec(),
+        _ => Vec::new(),
+      };
+      entries.push(entry);
+    }
+
+    info!("parsed changelog with {} entries", entries.len());
+    Ok(Changelog { entries })
+  }
+}
+
+/// Parse `package (version) distributions; key=value, ...`.
+///
+/// The rest of the entry is left empty, for the caller to fill in.
+fn parse_header(source: &str, line: &Line) -> eyre::Result<ChangelogEntry> {
+  let error = |span, message: String| span::error(source, span, message);
+  let text = line.text;
+
+  let (package, rest) = text.split_once(" (").ok_or_else(|| {
+    error(
+      line.span(),
+      "expected `package (version)` entry header".into(),
+    )
+  })?;
+  if package.is_empty() || package.contains(char::is_whitespace) {
+    return Err(error(
+      line.span_at(0, package.len()),
+      format!("bad package name {:?}", package),
+    ));
+  }
+  let (version, rest) = rest
+    .split_once(')')
+    .ok_or_else(|| error(line.span(), "unclosed `(` around version".into()))?;
+  let version_span = line.span_at(package.len() + 2, version.len());
+  let version = Version::from_str(version)
+    .map_err(|err| error(version_span, format!("{:#}", err)))?;
+  let (distributions, metadata) = rest
+    .split_once(';')
+    .ok_or_else(|| error(line.span(), "no `;` after distributions".into()))?;
+  let distributions: Vec<String> = distributions
+    .split_whitespace()
+    .map(str::to_owned)
+    .collect();
+  if distributions.is_empty() {
+    return Err(error(line.span(), "entry has no distributions".into()));
+  }
+
+  let mut urgency = None;
+  let mut extra = Vec::new();
+  // `metadata` is the end of the line, so this is where it starts
+  let mut offset = text.len() - metadata.len();
+  for raw in metadata.split(',') {
+    let pair = raw.trim();
+    let pair_span =
+      line.span_at(offset + raw.len() - raw.trim_start().len(), pair.len());
+    offset += raw.len() + 1;
+    if pair.is_empty() {
+      continue;
+    }
+    let (key, value) = pair.split_once('=').ok_or_else(|| {
+      error(pair_span, format!("expected `key=value`, found {:?}", pair))
+    })?;
+    let (key, value) = (key.trim(), value.trim().to_owned());
+    if key.eq_ignore_ascii_case("urgency") {
+      urgency = Some(value);
+    } else {
+      extra.push((key.to_owned(), value));
+    }
+  }
+
+  Ok(ChangelogEntry {
+    package: package.to_owned(),
+    version,
+    distributions,
+    urgency,
+    extra,
+    changes: Vec::new(),
+    maintainer: String::new(),
+    date: ChangelogDate {
+      year: 1970,
+      month: 1,
+      day: 1,
+      hour: 0,
+      minute: 0,
+      second: 0,
+      offset: 0,
+    },
+  })
+}
+
+/// Parse ` -- Name <email>  date` into `entry`.
+fn parse_trailer(
+  source: &str,
+  line: &Line,
+  entry: &mut ChangelogEntry,
+) -> eyre::Result<()> {
+  let text = line.text;
+  let rest = text.strip_prefix(" -- ").ok_or_else(|| {
+    span::error(source, line.span(), "trailer line must start with ` -- `")
+  })?;
+  let email_end = rest
+    .find('>')
+    .filter(|&idx| rest[..idx].contains('<'))
+    .ok_or_else(|| {
+      span::error(
+        source,
+        line.span(),
+        "expected `Name <email>` in trailer line",
+      )
+    })?;
+  let (maintainer, date) = rest.split_at(email_end + 1);
+  if !date.starts_with("  ") {
+    warn!(
+      "at {}: there should be two spaces between the maintainer and date",
+      line.span()
+    );
+  }
+  entry.maintainer = maintainer.trim().to_owned();
+  let date_start = text.len() - date.trim_start().len();
+  let date = date.trim();
+  entry.date = ChangelogDate::from_str(date).map_err(|err| {
+    span::error(
+      source,
+      line.span_at(date_start, date.len()),
+      format!("{:#}", err),
+    )
+  })?;
+  Ok(())
+}
+
+// Writing.
+
+impl std::fmt::Display for Changelog {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (idx, entry) in self.entries.iter().enumerate() {
+      if idx != 0 {
+        writeln!(f)?;
+      }
+      write!(f, "{}", entry)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::fmt::Display for ChangelogEntry {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} ({}) {};",
+      &self.package,
+      &self.version,
+      self.distributions.join(" ")
+    )?;
+    let pairs = self
+      .urgency
+      .iter()
+      .map(|urgency| ("urgency", urgency.as_str()))
+      .chain(self.extra.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    for (idx, (key, value)) in pairs.enumerate() {
+      let sep = if idx == 0 { " " } else { ", " };
+      write!(f, "{}{}={}", sep, key, value)?;
+    }
+    writeln!(f)?;
+    writeln!(f)?;
+    for line in &self.changes {
+      writeln!(f, "{}", line)?;
+    }
+    writeln!(f)?;
+    writeln!(f, " -- {}  {}", &self.maintainer, &self.date)
+  }
+}
@@ -0,0 +1,64 @@
+//! Reading compressed input: `.xz`, `.gz`, or not compressed at all.
+//!
+//! The format is picked from the first few bytes, not the file name, so
+//! a misnamed file still reads fine.
+
+use std::{
+  io::{BufRead, BufReader, Read},
+  path::Path,
+};
+
+use eyre::{Context, eyre};
+use flate2::bufread::MultiGzDecoder;
+use xz2::bufread::XzDecoder;
+
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+  Xz,
+  Gzip,
+  None,
+}
+
+impl Compression {
+  /// Tell the format from the start of the data.
+  pub fn detect(start: &[u8]) -> Self {
+    if start.starts_with(XZ_MAGIC) {
+      Compression::Xz
+    } else if start.starts_with(GZIP_MAGIC) {
+      Compression::Gzip
+    } else {
+      Compression::None
+    }
+  }
+
+  /// Wrap `input` to decompress it as this format.
+  pub fn decoder<'a, R: BufRead + 'a>(self, input: R) -> Box<dyn Read + 'a> {
+    match self {
+      Compression::Xz => Box::new(XzDecoder::new_multi_decoder(input)),
+      Compression::Gzip => Box::new(MultiGzDecoder::new(input)),
+      Compression::None => Box::new(input),
+    }
+  }
+}
+
+/// Decompress `input`, whatever it's compressed with.
+pub fn decompress<'a, R: BufRead + 'a>(
+  mut input: R,
+) -> std::io::Result<Box<dyn Read + 'a>> {
+  let compression = Compression::detect(input.fill_buf()?);
+  Ok(compression.decoder(input))
+}
+
+/// Open the file at `path` and decompress it, whatever it's compressed
+/// with.
+pub fn open<P: AsRef<Path>>(path: P) -> eyre::Result<Box<dyn BufRead>> {
+  let path = path.as_ref();
+  let file = std::fs::File::open(path)
+    .wrap_err_with(|| eyre!("could not open {}", path.display()))?;
+  let reader = decompress(BufReader::new(file))
+    .wrap_err_with(|| eyre!("could not read {}", path.display()))?;
+  Ok(Box::new(BufReader::new(reader)))
+}
@@ -0,0 +1,144 @@
+//! Convert deb822 files to JSON or TOML, and back.
+//!
+//! Generic files are a list of stanzas, each a map from field name to
+//! value. With `--copyright`, the file goes through [`CopyrightFile`]
+//! instead, which gives the structured copyright model.
+
+use std::{
+  io::{Read, Write},
+  path::PathBuf,
+  str::FromStr,
+};
+
+use clap::{Args, Subcommand};
+use eyre::{Context, eyre};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::deb822::{Deb822File, copyright::CopyrightFile};
+
+/// Convert deb822 files to and from JSON or TOML.
+#[derive(Args)]
+pub struct Convert {
+  #[command(subcommand)]
+  direction: Direction,
+}
+
+#[derive(Subcommand)]
+enum Direction {
+  /// Read a deb822 file and write it as JSON.
+  #[command(name = "to-json")]
+  ToJson(ConvertArgs),
+  /// Read JSON and write it as a deb822 file.
+  #[command(name = "from-json")]
+  FromJson(ConvertArgs),
+  /// Read a deb822 file and write it as TOML.
+  #[command(name = "to-toml")]
+  ToToml(ConvertArgs),
+  /// Read TOML and write it as a deb822 file.
+  #[command(name = "from-toml")]
+  FromToml(ConvertArgs),
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+  /// File to read. [default: stdin]
+  input: Option<PathBuf>,
+  /// File to write. [default: stdout]
+  #[arg(short, long)]
+  output: Option<PathBuf>,
+  /// Use the copyright file model, instead of plain stanzas.
+  #[arg(short, long)]
+  copyright: bool,
+}
+
+/// The formats deb822 can be converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+  Json,
+  Toml,
+}
+
+impl DataFormat {
+  pub fn serialize<T: Serialize>(self, value: &T) -> eyre::Result<String> {
+    Ok(match self {
+      DataFormat::Json => {
+        let mut out = serde_json::to_string_pretty(value)?;
+        out.push('\n');
+        out
+      }
+      DataFormat::Toml => toml::to_string_pretty(value)?,
+    })
+  }
+
+  pub fn deserialize<T: DeserializeOwned>(self, s: &str) -> eyre::Result<T> {
+    Ok(match self {
+      DataFormat::Json => serde_json::from_str(s)?,
+      DataFormat::Toml => toml::from_str(s)?,
+    })
+  }
+}
+
+/// Parse a deb822 file, and write it out as `format`.
+pub fn from_deb822(
+  source: &str,
+  format: DataFormat,
+  copyright: bool,
+) -> eyre::Result<String> {
+  if copyright {
+    format.serialize(&CopyrightFile::from_str(source)?)
+  } else {
+    format.serialize(&Deb822File::from_str(source)?)
+  }
+}
+
+/// Read data in `format`, and write it out as a deb822 file.
+pub fn to_deb822(
+  source: &str,
+  format: DataFormat,
+  copyright: bool,
+) -> eyre::Result<String> {
+  Ok(if copyright {
+    format.deserialize::<CopyrightFile>(source)?.to_string()
+  } else {
+    format.deserialize::<Deb822File>(source)?.to_string()
+  })
+}
+
+impl Convert {
+  pub fn do_it(self) -> eyre::Result<()> {
+    let (args, format, to_data) = match self.direction {
+      Direction::ToJson(args) => (args, DataFormat::Json, true),
+      Direction::FromJson(args) => (args, DataFormat::Json, false),
+      Direction::ToToml(args) => (args, DataFormat::Toml, true),
+      Direction::FromToml(args) => (args, DataFormat::Toml, false),
+    };
+
+    let mut source = String::new();
+    match &args.input {
+      Some(path) => {
+        source = std::fs::read_to_string(path).wrap_err_with(|| {
+          eyre!("could not read input file at {}", path.display())
+        })?;
+      }
+      None => {
+        std::io::stdin()
+          .read_to_string(&mut source)
+          .wrap_err("could not read stdin")?;
+      }
+    }
+
+    let out = if to_data {
+      from_deb822(&source, format, args.copyright)?
+    } else {
+      to_deb822(&source, format, args.copyright)?
+    };
+
+    match &args.output {
+      Some(path) => std::fs::write(path, out).wrap_err_with(|| {
+        eyre!("could not write output file at {}", path.display())
+      })?,
+      None => std::io::stdout().write_all(out.as_bytes())?,
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,154 @@
+//! Check that every file in an orig tarball is covered by a `Files`
+//! paragraph of the copyright file.
+
+use std::{
+  io::Read,
+  path::{Path, PathBuf},
+};
+
+use clap::Args;
+use eyre::{Context, eyre};
+
+use crate::{
+  deb822::copyright::{self, CopyrightFile},
+  glob::Glob,
+  strip::{Decision, Stripper},
+};
+
+/// List files in the upstream tarball that no `Files` paragraph covers,
+/// and `Files` globs that don't match anything.
+///
+/// Files removed by `Files-Excluded` don't need to be covered.
+/// Exits with code `2` if anything was found.
+#[derive(Args)]
+pub struct Coverage {
+  /// Original tar.xz file.
+  #[arg(short, long)]
+  input: PathBuf,
+  /// Path to the debian copyright file.
+  /// [default: ./debian/copyright]
+  #[arg(short, long)]
+  debfile: Option<PathBuf>,
+}
+
+impl Coverage {
+  pub fn do_it(self) -> eyre::Result<()> {
+    let copyright = CopyrightFile::from_path(
+      self
+        .debfile
+        .unwrap_or(PathBuf::from(copyright::DEFAULT_PATH)),
+    )?;
+    let in_file = std::fs::File::options()
+      .read(true)
+      .open(&self.input)
+      .wrap_err_with(|| {
+        eyre!("could not open input file at {}", self.input.display())
+      })?;
+
+    let report = check_coverage(&copyright, in_file)?;
+    for path in &report.uncovered {
+      println!("uncovered: {}", path.display());
+    }
+    for unused in &report.unused_globs {
+      println!(
+        "unused glob: {} (in Files paragraph {})",
+        unused.glob,
+        unused.paragraph + 1
+      );
+    }
+
+    if !report.is_clean() {
+      std::process::exit(2);
+    }
+    Ok(())
+  }
+}
+
+/// What [`check_coverage`] found.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+  /// Kept files that no `Files` paragraph matches, in archive order.
+  /// These paths have the leading `foo-1.0/` taken off.
+  pub uncovered: Vec<PathBuf>,
+  /// `Files` globs that don't match any kept file.
+  pub unused_globs: Vec<UnusedGlob>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnusedGlob {
+  /// Index into [`CopyrightFile::files`].
+  pub paragraph: usize,
+  pub glob: Glob,
+}
+
+impl CoverageReport {
+  /// Return if nothing is wrong.
+  pub fn is_clean(&self) -> bool {
+    self.uncovered.is_empty() && self.unused_globs.is_empty()
+  }
+}
+
+/// Read a `.tar.xz` from `input`, and check it against the `Files`
+/// paragraphs of `copyright`.
+///
+/// Only files count; directory entries are skipped.
+pub fn check_coverage<R: Read>(
+  copyright: &CopyrightFile,
+  input: R,
+) -> eyre::Result<CoverageReport> {
+  let mut kept_files = Vec::new();
+  Stripper::new(copyright)
+    .on_entry(|entry, decision| {
+      if decision == Decision::Keep && !entry.header.entry_type().is_dir() {
+        kept_files.push(entry.path.to_path_buf());
+      }
+      decision
+    })
+    .dry_run(input)?;
+
+  Ok(coverage_of(copyright, &kept_files))
+}
+
+/// Check a list of paths against the `Files` paragraphs of `copyright`.
+pub fn coverage_of<P: AsRef<Path>>(
+  copyright: &CopyrightFile,
+  paths: &[P],
+) -> CoverageReport {
+  let mut report = CoverageReport::default();
+  let mut glob_used: Vec<Vec<bool>> = copyright
+    .files
+    .iter()
+    .map(|paragraph| vec![false; paragraph.files.len()])
+    .collect();
+
+  for path in paths {
+    let path = path.as_ref();
+    let mut covered = false;
+    for (paragraph, used) in copyright.files.iter().zip(&mut glob_used) {
+      for (glob, used) in paragraph.files.iter().zip(used.iter_mut()) {
+        if glob.matches(path) {
+          *used = true;
+          covered = true;
+        }
+      }
+    }
+    if !covered {
+      report.uncovered.push(path.to_path_buf());
+    }
+  }
+
+  for (paragraph_idx, (paragraph, used)) in
+    copyright.files.iter().zip(&glob_used).enumerate()
+  {
+    for (glob, used) in paragraph.files.iter().zip(used) {
+      if !used {
+        report.unused_globs.push(UnusedGlob {
+          paragraph: paragraph_idx,
+          glob: glob.clone(),
+        });
+      }
+    }
+  }
+
+  report
+}
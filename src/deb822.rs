@@ -7,12 +7,25 @@
 //! IMHO.
 //! The source is here: https://github.com/jelmer/deb822-rs
 
+pub mod autopkgtest;
+pub mod changes;
+pub mod checksums;
+pub mod control;
 pub mod copyright;
+pub mod dsc;
+pub mod lossless;
+pub mod signed;
+pub mod stream;
 
-use std::{collections::HashMap, str::FromStr};
+use std::{borrow::Cow, fmt::Write, iter::Peekable, str::FromStr};
 
-use eyre::{OptionExt, eyre};
-use log::{info, trace};
+use eyre::eyre;
+use log::{info, trace, warn};
+
+use crate::{
+  relation::Relations,
+  span::{self, Line, Span, SpannedError},
+};
 
 // Parsing.
 // Before we enter any `eat` function, comment lines are stripped.
@@ -22,239 +35,865 @@ use log::{info, trace};
 /// and friends consider newlines to be whitespace.
 const WHITESPACE: &[char] = &[' ', '\t'];
 
-#[derive(Debug, Clone)]
-pub struct Deb822File {
-  stanzas: Vec<Stanza>,
+/// A parsed file.
+///
+/// Names and values borrow from the text the file was parsed from where
+/// they can, so parsing a big file doesn't copy all of it. Use
+/// [`Deb822File::into_owned`] to keep it around longer than the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Deb822File<'a> {
+  stanzas: Vec<Stanza<'a>>,
+}
+
+/// What to do when a field name appears more than once in a stanza.
+///
+/// Policy says field names are case-insensitive and must be unique
+/// within a stanza, so `Files-Excluded` and `files-excluded` are the
+/// same field and can't both be there. Some real-world files break
+/// that rule anyways.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateFields {
+  /// Fail to parse, like policy says.
+  #[default]
+  Error,
+  /// Keep every copy, in file order. [`Stanza::get`] finds the first
+  /// one, and [`Stanza::get_all`] finds all of them.
+  Keep,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stanza<'a> {
+  // Field names are case-insensitive, so every lookup here ignores
+  // ASCII case. Names are kept as written, for writing back out.
+  // This is a Vec and not a map so that the order of the fields is kept
+  // when writing the stanza back out. Stanzas are small, so linear
+  // lookups are fine.
+  /// Field names and the field data, in the order they appear in the file.
+  fields: Vec<(Cow<'a, str>, Field<'a>)>,
+  span: Option<Span>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Field<'a> {
+  pub same_line_value: Option<Cow<'a, str>>,
+  /// The continuation lines, with the indentation they all share taken
+  /// off, so any extra indentation is kept. ` .` lines are empty strings.
+  pub list_values: Vec<Cow<'a, str>>,
+  /// Where the field was in the file it was parsed from, from the start
+  /// of the name to the end of the last continuation line. `None` if it
+  /// wasn't parsed.
+  pub span: Option<Span>,
+  /// Where each line of [`Field::iter_lines`] was, in the same order.
+  /// Each span covers just the value text, so ` .` lines get an empty
+  /// span. Empty if the field wasn't parsed.
+  pub value_spans: Vec<Span>,
+}
+
+// Spans are left out of equality, so a stanza equals the same stanza
+// parsed from somewhere else in a file, or built by hand.
+
+impl PartialEq for Stanza<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    self.fields == other.fields
+  }
+}
+
+impl Eq for Stanza<'_> {}
+
+impl PartialEq for Field<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    self.same_line_value == other.same_line_value
+      && self.list_values == other.list_values
+  }
 }
 
-#[derive(Debug, Clone)]
-pub struct Stanza {
-  // The docs are silent on whether duplicate field names are allowed.
-  // For simplicity I will make this a HashMap
-  /// Maps a field name to the field data.
-  pub fields: HashMap<String, Field>,
+impl Eq for Field<'_> {}
+
+/// How a field's value is spread over its lines, from the "Syntax of
+/// control files" section of policy.
+///
+/// The parser doesn't know which fields are which, since that depends on
+/// the kind of file, so this is for the code reading the values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+  /// One line only, like `Format` or `Upstream-Name`.
+  Simple,
+  /// A single logical line that may be wrapped, like `Files` or
+  /// `Build-Depends`. The line breaks mean nothing.
+  Folded,
+  /// Several lines, like `License` or `Description`. The line breaks,
+  /// empty lines and extra indentation all mean something.
+  Multiline,
+}
+
+impl<'a> Deb822File<'a> {
+  pub fn new(stanzas: Vec<Stanza<'a>>) -> Self {
+    Self { stanzas }
+  }
+
+  /// Parse `s`, handling duplicate field names the way `duplicates` says.
+  /// [`FromStr`] is the same as [`DuplicateFields::Error`], but copies
+  /// everything so that the file doesn't borrow from `s`.
+  ///
+  /// If `s` is clearsigned, the signature is ignored and only the signed
+  /// body is parsed. Use [`signed::SignedFile`] to check the signature.
+  pub fn parse(s: &'a str, duplicates: DuplicateFields) -> eyre::Result<Self> {
+    let (file, errors) = Self::parse_inner(s, duplicates, false);
+    match errors.into_iter().next() {
+      Some(error) => Err(error.into()),
+      None => Ok(file),
+    }
+  }
+
+  /// Like [`Deb822File::parse`], but carry on past errors, to find all of
+  /// them in one go.
+  ///
+  /// A stanza with an error in it is skipped, up to the next blank line,
+  /// so the file that comes back has every stanza that parsed. If the
+  /// signature envelope is broken, nothing parses.
+  pub fn parse_recovering(
+    s: &'a str,
+    duplicates: DuplicateFields,
+  ) -> (Self, Vec<SpannedError>) {
+    Self::parse_inner(s, duplicates, true)
+  }
+
+  fn parse_inner(
+    s: &'a str,
+    duplicates: DuplicateFields,
+    recover: bool,
+  ) -> (Self, Vec<SpannedError>) {
+    let parser = Parser {
+      source: s,
+      duplicates,
+    };
+    let mut stanzas = Vec::new();
+    let mut errors = Vec::new();
+
+    // The body lines still know where they are in `s`, so positions in
+    // errors are positions in the signed file.
+    let signed_body = match signed::split(s) {
+      Ok(signed) => signed.map(|signed| signed.body),
+      Err(error) => {
+        errors.push(error);
+        return (Deb822File { stanzas }, errors);
+      }
+    };
+    let lines: Box<dyn Iterator<Item = Line>> = match signed_body {
+      Some(body) => Box::new(body.into_iter()),
+      None => Box::new(span::lines(s)),
+    };
+    let mut lines = lines
+      .filter(|line| !line.text.trim_start().starts_with('#'))
+      .peekable();
+
+    while lines.peek().is_some() {
+      match parser.eat_stanza(&mut lines) {
+        Ok(stanza) => stanzas.push(stanza),
+        Err(error) => {
+          errors.push(error);
+          if !recover {
+            break;
+          }
+          // Stanzas can only end at a blank line, so that's the first
+          // place that is safe to start again
+          for line in lines.by_ref() {
+            if line.text.trim().is_empty() {
+              break;
+            }
+          }
+          while lines.next_if(|line| line.text.trim().is_empty()).is_some() {}
+        }
+      }
+    }
+
+    info!(
+      "parsed Deb822 file with {} stanzas and {} errors",
+      stanzas.len(),
+      errors.len()
+    );
+    (Deb822File { stanzas }, errors)
+  }
+
+  /// Copy everything that is still borrowed.
+  pub fn into_owned(self) -> Deb822File<'static> {
+    Deb822File {
+      stanzas: self.stanzas.into_iter().map(Stanza::into_owned).collect(),
+    }
+  }
+
+  pub fn stanzas(&self) -> &[Stanza<'a>] {
+    &self.stanzas
+  }
 }
 
-#[derive(Debug, Clone)]
-pub struct Field {
-  pub same_line_value: Option<String>,
-  pub list_values: Vec<String>,
+impl<'a> Stanza<'a> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Get the field named `name`, ignoring case.
+  ///
+  /// If there are several (see [`DuplicateFields::Keep`]), this is the
+  /// first one.
+  pub fn get(&self, name: &str) -> Option<&Field<'a>> {
+    self.get_all(name).next()
+  }
+
+  /// Get every field named `name`, ignoring case, in order.
+  pub fn get_all<'s, 'n>(
+    &'s self,
+    name: &'n str,
+  ) -> impl Iterator<Item = &'s Field<'a>> + use<'a, 's, 'n> {
+    self
+      .fields
+      .iter()
+      .filter(move |(field_name, _)| field_name.eq_ignore_ascii_case(name))
+      .map(|(_, field)| field)
+  }
+
+  /// Set the field named `name`, ignoring case.
+  ///
+  /// If there already was one, it is replaced in the same position, under
+  /// the new name, and the old value is returned. Otherwise the field goes
+  /// on the end.
+  pub fn insert(
+    &mut self,
+    name: impl Into<Cow<'a, str>>,
+    field: Field<'a>,
+  ) -> Option<Field<'a>> {
+    let name = name.into();
+    match self
+      .fields
+      .iter_mut()
+      .find(|(field_name, _)| field_name.eq_ignore_ascii_case(&name))
+    {
+      Some(slot) => Some(std::mem::replace(slot, (name, field)).1),
+      None => {
+        self.fields.push((name, field));
+        None
+      }
+    }
+  }
+
+  /// Add a field to the end, even if there already is one with that name.
+  pub fn push(&mut self, name: impl Into<Cow<'a, str>>, field: Field<'a>) {
+    self.fields.push((name.into(), field));
+  }
+
+  /// Iterate over the field names and fields, in order.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &Field<'a>)> + '_ {
+    self
+      .fields
+      .iter()
+      .map(|(name, field)| (name.as_ref(), field))
+  }
+
+  /// Where the stanza was in the file it was parsed from, from the start
+  /// of its first field to the end of its last. `None` if it wasn't
+  /// parsed.
+  pub fn span(&self) -> Option<Span> {
+    self.span
+  }
+
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  /// Move the spans, for a stanza parsed out of a piece of a bigger file.
+  /// See [`Span::shifted`].
+  pub(crate) fn shift_spans(&mut self, offset: usize, line: usize) {
+    self.span = self.span.map(|span| span.shifted(offset, line));
+    for (_, field) in &mut self.fields {
+      field.span = field.span.map(|span| span.shifted(offset, line));
+      for span in &mut field.value_spans {
+        *span = span.shifted(offset, line);
+      }
+    }
+  }
+
+  /// Copy everything that is still borrowed.
+  pub fn into_owned(self) -> Stanza<'static> {
+    Stanza {
+      fields: self
+        .fields
+        .into_iter()
+        .map(|(name, field)| {
+          (Cow::Owned(name.into_owned()), field.into_owned())
+        })
+        .collect(),
+      span: self.span,
+    }
+  }
 }
 
-impl Field {
+impl Field<'_> {
   /// Convenience function that chains over `same_line_value`
   /// and `list_values`
-  pub fn iter_lines(&self) -> impl Iterator<Item = &String> + '_ {
-    self.same_line_value.iter().chain(self.list_values.iter())
+  pub fn iter_lines(&self) -> impl Iterator<Item = &str> + '_ {
+    self
+      .same_line_value
+      .iter()
+      .chain(self.list_values.iter())
+      .map(|line| line.as_ref())
+  }
+
+  /// The value, read as a field of this kind.
+  ///
+  /// Simple and folded values are one line, with the lines joined by
+  /// spaces. Multiline values are the lines joined by `\n`, starting with
+  /// the same-line value if there is one.
+  pub fn value(&self, kind: FieldKind) -> String {
+    let lines = self.iter_lines();
+    match kind {
+      FieldKind::Simple | FieldKind::Folded => lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" "),
+      FieldKind::Multiline => lines.collect::<Vec<_>>().join("\n"),
+    }
+  }
+
+  /// Parse the value as a relation field, like `Depends`.
+  pub fn relations(&self) -> eyre::Result<Relations> {
+    Relations::from_str(&self.value(FieldKind::Folded))
+  }
+
+  /// Copy everything that is still borrowed.
+  pub fn into_owned(self) -> Field<'static> {
+    let owned = |line: Cow<str>| Cow::Owned(line.into_owned());
+    Field {
+      same_line_value: self.same_line_value.map(owned),
+      list_values: self.list_values.into_iter().map(owned).collect(),
+      span: self.span,
+      value_spans: self.value_spans,
+    }
   }
 }
 
-impl FromStr for Deb822File {
-  type Err = eyre::Error;
+/// Turn raw continuation lines, each starting with whitespace, into
+/// [`Field::list_values`].
+///
+/// Policy says a line of just ` .` is an empty line, since a truly empty
+/// line would end the stanza. The indentation every other line shares is
+/// taken off, so that indented parts of license texts stay indented.
+pub(crate) fn continuation_values<'a>(
+  lines: impl IntoIterator<Item = &'a str>,
+) -> Vec<Cow<'a, str>> {
+  let lines: Vec<&str> = lines.into_iter().map(str::trim_end).collect();
+  let is_dot = |line: &str| line.trim_start_matches(WHITESPACE) == ".";
+  let indent = lines
+    .iter()
+    .filter(|line| !is_dot(line))
+    .map(|line| line.len() - line.trim_start_matches(WHITESPACE).len())
+    .min()
+    .unwrap_or(0);
+  lines
+    .into_iter()
+    .map(|line| {
+      if is_dot(line) {
+        Cow::Borrowed("")
+      } else {
+        // The indentation is all single-byte characters
+        Cow::Borrowed(&line[indent..])
+      }
+    })
+    .collect()
+}
 
-  fn from_str(s: &str) -> Result<Self, Self::Err> {
-    let meta = ParseMeta { source: s };
+// Writing.
+// This is the inverse of parsing, with the caveat that there are values
+// it is impossible to write correctly: field names with `:` or whitespace
+// in them, lines with newlines in them, continuation lines starting
+// with `#` (those read back as comments), continuation lines that are
+// just `.` (those read back as empty), and fields whose continuation
+// lines are all indented (that indentation is shared, so it goes away).
 
-    let lines: Vec<&str> = s
-      .split('\n')
-      .filter(|line| !line.trim_start().starts_with('#'))
-      .collect();
-    let mut lines_slice = lines.as_slice();
+/// Writes stanzas separated by blank lines.
+impl std::fmt::Display for Deb822File<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (idx, stanza) in self.stanzas.iter().enumerate() {
+      if idx != 0 {
+        f.write_char('\n')?;
+      }
+      std::fmt::Display::fmt(stanza, f)?;
+    }
+    Ok(())
+  }
+}
 
-    let mut stanzas = Vec::new();
-    while !lines_slice.is_empty() {
-      // an error here does mean abort, because the only
-      // way to safely end a file is to reach the end
-      // cleanly w/o dangling whatever
-      let (next_lines_slice, stanza) = meta.eat_stanza(lines_slice)?;
-      stanzas.push(stanza);
-      lines_slice = next_lines_slice;
+/// Writes one line per field header and one per continuation line,
+/// each ending in a newline.
+///
+/// Continuation lines are indented with one space, on top of any
+/// indentation they already have. Empty continuation lines are written
+/// as ` .`, because an empty line would end the stanza.
+impl std::fmt::Display for Stanza<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (name, field) in &self.fields {
+      f.write_str(name)?;
+      f.write_char(':')?;
+      if let Some(value) = &field.same_line_value {
+        write!(f, " {}", value)?;
+      }
+      f.write_char('\n')?;
+      for line in &field.list_values {
+        if line.is_empty() {
+          f.write_str(" .\n")?;
+        } else {
+          writeln!(f, " {}", line)?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+// Helpers for going between fields and typed values, for the typed views
+// in the submodules.
+// Text values are stored with the lines joined by `\n`, the first line
+// being the one on the same line as the field name.
+
+pub(crate) fn field_text(field: &Field) -> String {
+  field.iter_lines().collect::<Vec<_>>().join("\n")
+}
+
+pub(crate) fn field_lines(field: Option<&Field>) -> Vec<String> {
+  field
+    .map(|field| field.iter_lines().map(str::to_owned).collect())
+    .unwrap_or_default()
+}
+
+/// Split a field on whitespace, across all its lines.
+pub(crate) fn field_words(field: Option<&Field>) -> Vec<String> {
+  field
+    .into_iter()
+    .flat_map(Field::iter_lines)
+    .flat_map(|line| line.split_ascii_whitespace())
+    .map(str::to_owned)
+    .collect()
+}
+
+/// Split a field on commas, across all its lines, trimming each entry.
+/// Empty entries, like after a trailing comma, are skipped.
+pub(crate) fn field_list(field: Option<&Field>) -> Vec<String> {
+  field
+    .map(|field| field.iter_lines().collect::<Vec<_>>().join(" "))
+    .iter()
+    .flat_map(|joined| joined.split(','))
+    .map(str::trim)
+    .filter(|entry| !entry.is_empty())
+    .map(str::to_owned)
+    .collect()
+}
+
+/// Parse a relation field, if it's there.
+pub(crate) fn field_relations(
+  stanza: &Stanza,
+  name: &str,
+) -> eyre::Result<Relations> {
+  match stanza.get(name) {
+    Some(field) => field
+      .relations()
+      .map_err(|err| err.wrap_err(eyre!("in {}", name))),
+    None => Ok(Relations::default()),
+  }
+}
+
+/// Get a field that has to be there, as text.
+pub(crate) fn required_text(
+  stanza: &Stanza,
+  name: &str,
+  kind: &str,
+) -> eyre::Result<String> {
+  stanza
+    .get(name)
+    .map(field_text)
+    .ok_or_else(|| eyre!("{} stanza has no `{}` field", kind, name))
+}
+
+/// Copy the fields not named in `known` into a new stanza.
+pub(crate) fn extra_fields(
+  stanza: &Stanza<'_>,
+  known: &[&str],
+) -> Stanza<'static> {
+  let mut out = Stanza::new();
+  for (name, field) in stanza.iter() {
+    if !known.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+      out.push(name.to_owned(), field.clone().into_owned());
     }
+  }
+  out
+}
+
+pub(crate) fn insert_text(stanza: &mut Stanza, name: &str, text: Option<&str>) {
+  if let Some(text) = text {
+    let mut lines = text.split('\n').map(str::to_owned);
+    stanza.insert(
+      name.to_owned(),
+      Field {
+        same_line_value: lines
+          .next()
+          .filter(|line| !line.is_empty())
+          .map(Cow::Owned),
+        list_values: lines.map(Cow::Owned).collect(),
+        span: None,
+        value_spans: Vec::new(),
+      },
+    );
+  }
+}
 
-    info!("parsed Deb822 file with {} stanzas", stanzas.len());
-    Ok(Deb822File { stanzas })
+/// Write text all on continuation lines, with nothing on the same line
+/// as the field name, like `Changes` in a `.changes` file.
+pub(crate) fn insert_block(
+  stanza: &mut Stanza,
+  name: &str,
+  text: Option<&str>,
+) {
+  if let Some(text) = text {
+    stanza.insert(
+      name.to_owned(),
+      Field {
+        same_line_value: None,
+        list_values: text
+          .split('\n')
+          .map(|line| Cow::Owned(line.to_owned()))
+          .collect(),
+        span: None,
+        value_spans: Vec::new(),
+      },
+    );
   }
 }
 
-struct ParseMeta<'source> {
-  source: &'source str,
+pub(crate) fn insert_lines(stanza: &mut Stanza, name: &str, lines: &[String]) {
+  if !lines.is_empty() {
+    insert_text(stanza, name, Some(&lines.join("\n")));
+  }
 }
 
-impl<'source> ParseMeta<'source> {
-  /// If `fragment` is within this string, return the row and column
-  /// that it starts at. Note these are 0-indexed.
-  fn find_fragment_row_col(&self, fragment: &str) -> Option<(usize, usize)> {
-    // This is the evil part.
-    // It CANNOT be done with string searching, as there are duplicate
-    // substrings in the source.
-    let self_start = self.source.as_ptr() as usize;
-    let frag_start = fragment.as_ptr() as usize;
+/// Write words space-separated on the same line.
+pub(crate) fn insert_words(stanza: &mut Stanza, name: &str, words: &[String]) {
+  if !words.is_empty() {
+    insert_text(stanza, name, Some(&words.join(" ")));
+  }
+}
 
-    let slice_ok = self_start <= frag_start
-      && (self_start + self.source.len()) >= (frag_start + fragment.len());
-    if !slice_ok {
-      None
-    } else {
-      // Ok we know that fragment comes from self.
-      let frag_offset = frag_start - self_start;
-      let row_col = self
-        .source
-        .char_indices()
-        .filter_map(
-          |(byte_idx, ch)| {
-            if ch == '\n' { Some(byte_idx) } else { None }
-          },
-        )
-        .take_while(|byte_idx| *byte_idx < frag_offset)
-        .enumerate()
-        .last()
-        .map(|(nl_count, last_nl_char_idx)| {
-          (nl_count + 1, frag_offset - last_nl_char_idx)
-        })
-        .unwrap_or((0, 0));
-      Some(row_col)
+/// Write a comma-separated list. One entry goes on the same line, several
+/// go one per line, like `wrap-and-sort` does.
+pub(crate) fn insert_list(stanza: &mut Stanza, name: &str, entries: &[String]) {
+  match entries {
+    [] => {}
+    [entry] => insert_text(stanza, name, Some(entry)),
+    _ => {
+      let last = entries.len() - 1;
+      stanza.insert(
+        name.to_owned(),
+        Field {
+          same_line_value: None,
+          list_values: entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+              if idx == last {
+                Cow::Owned(entry.clone())
+              } else {
+                Cow::Owned(format!("{},", entry))
+              }
+            })
+            .collect(),
+          span: None,
+          value_spans: Vec::new(),
+        },
+      );
     }
   }
+}
 
-  fn rowcol_fmt(&self, fragment: &str) -> String {
-    if let Some((row, col)) = self.find_fragment_row_col(fragment) {
-      format!("{}:{}", row + 1, col)
-    } else {
-      "?:?".to_string()
+/// Write a relation field one entry per line, like [`insert_list`].
+pub(crate) fn insert_relations(
+  stanza: &mut Stanza,
+  name: &str,
+  relations: &Relations,
+) {
+  let entries: Vec<String> =
+    relations.iter().map(|entry| entry.to_string()).collect();
+  insert_list(stanza, name, &entries);
+}
+
+// Serde.
+// A stanza is a map from field name to value, in file order. A field is
+// one string: the same-line value, then a newline before each continuation
+// line. So a field with no same-line value starts with a newline.
+// This loses the difference between no same-line value and an empty one,
+// which the parser never makes anyway.
+// Stanzas with duplicate fields (see `DuplicateFields::Keep`) can't be
+// serialized, since a map can't hold the same key twice.
+// Deserialized stanzas own all their text.
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Stanza<'_> {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    // Maps can't have the same key twice, and deserializing rejects it
+    for (idx, (name, _)) in self.fields.iter().enumerate() {
+      if let Some((prev, _)) = self.fields[..idx]
+        .iter()
+        .find(|(prev, _)| prev.eq_ignore_ascii_case(name))
+      {
+        return Err(serde::ser::Error::custom(format!(
+          "duplicate field {:?} (the first one is {:?})",
+          name, prev
+        )));
+      }
+    }
+    s.collect_map(self.iter())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Stanza<'_> {
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    struct StanzaVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for StanzaVisitor {
+      type Value = Stanza<'static>;
+
+      fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a map of field names to values")
+      }
+
+      fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+      ) -> Result<Stanza<'static>, A::Error> {
+        let mut stanza = Stanza::new();
+        while let Some((name, field)) = map.next_entry::<String, Field>()? {
+          if stanza.get(&name).is_some() {
+            return Err(serde::de::Error::custom(format!(
+              "duplicate field {:?}",
+              name
+            )));
+          }
+          stanza.insert(name, field);
+        }
+        Ok(stanza)
+      }
     }
+
+    d.deserialize_map(StanzaVisitor)
   }
+}
 
-  fn eyre(&self, fragment: &str, error: eyre::Error) -> eyre::Error {
-    let row_col = self.rowcol_fmt(fragment);
-    error.wrap_err(format!("at {} ({})", row_col, fragment))
+#[cfg(feature = "serde")]
+impl serde::Serialize for Field<'_> {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    let mut out = self
+      .same_line_value
+      .as_deref()
+      .unwrap_or_default()
+      .to_owned();
+    for line in &self.list_values {
+      out.push('\n');
+      out.push_str(line);
+    }
+    s.serialize_str(&out)
   }
+}
 
-  // Parsing is below
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Field<'_> {
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    let s = String::deserialize(d)?;
+    let mut lines = s.split('\n');
+    let same_line_value = lines
+      .next()
+      .filter(|line| !line.is_empty())
+      .map(|line| Cow::Owned(line.to_owned()));
+    Ok(Field {
+      same_line_value,
+      list_values: lines.map(|line| Cow::Owned(line.to_owned())).collect(),
+      span: None,
+      value_spans: Vec::new(),
+    })
+  }
+}
 
-  fn eat_stanza<'a>(
+impl FromStr for Deb822File<'static> {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Deb822File::parse(s, DuplicateFields::Error).map(Deb822File::into_owned)
+  }
+}
+
+/// Turns lines into stanzas. Errors point at the lines in `source`.
+pub(crate) struct Parser<'s> {
+  pub(crate) source: &'s str,
+  pub(crate) duplicates: DuplicateFields,
+}
+
+impl<'s> Parser<'s> {
+  fn error(&self, span: Span, message: impl std::fmt::Display) -> SpannedError {
+    SpannedError::new(self.source, span, message)
+  }
+
+  /// Read a stanza, and the blank lines after it.
+  ///
+  /// Comment lines must already be gone from `lines`.
+  pub(crate) fn eat_stanza<I: Iterator<Item = Line<'s>>>(
     &self,
-    mut lines: &'a [&'a str],
-  ) -> eyre::Result<(&'a [&'a str], Stanza)> {
-    let mut out = Stanza {
-      fields: HashMap::new(),
-    };
+    lines: &mut Peekable<I>,
+  ) -> Result<Stanza<'s>, SpannedError> {
+    let mut out = Stanza::new();
 
-    while !lines.is_empty() {
-      let (rest, field_name, field) = self.eat_field(lines)?;
-      let prev = out.fields.insert(field_name.clone(), field);
-      if let Some(prev) = prev {
-        return Err(self.eyre(
-          &lines[0],
-          eyre!(
-            "duplicate key {} (previous had value {:?})",
-            &field_name,
-            &prev
-          ),
-        ));
+    while let Some(top_line) = lines.next() {
+      let (field_name, field) = self.eat_field(top_line, lines)?;
+      let prev = out
+        .fields
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(field_name.text));
+      match (prev, self.duplicates) {
+        (Some((prev_name, prev)), DuplicateFields::Error) => {
+          let message = if prev_name == field_name.text {
+            let prev_span = prev.span.unwrap_or_default();
+            format!(
+              "duplicate key {} (the first one is at {})",
+              field_name.text, prev_span
+            )
+          } else {
+            format!(
+              "duplicate key {} (differs only in case from {}, and field \
+               names are case-insensitive)",
+              field_name.text, prev_name
+            )
+          };
+          return Err(self.error(field_name.span(), message));
+        }
+        (Some((prev_name, _)), DuplicateFields::Keep) => {
+          warn!(
+            "at {}: keeping duplicate key {} (previous was {})",
+            field_name.span(),
+            field_name.text,
+            prev_name
+          );
+          out.push(field_name.text, field);
+        }
+        (None, _) => out.push(field_name.text, field),
       }
-      lines = rest;
 
       // After each field, if the next line is a newline, go to
       // the next stanza
-      if let Some(line) = lines.get(0)
-        && line.trim().is_empty()
-      {
-        trace!("finished a stanza, newline at {}", self.rowcol_fmt(line));
-        let nl_count = lines
-          .iter()
-          .take_while(|line| line.trim().is_empty())
-          .count();
-        lines = &lines[nl_count..];
+      let is_blank = |line: &Line| line.text.trim().is_empty();
+      if let Some(line) = lines.next_if(is_blank) {
+        trace!("finished a stanza, newline at line {}", line.number);
+        while lines.next_if(is_blank).is_some() {}
         break;
       }
     }
 
-    Ok((lines, out))
+    let first = out.fields.first().and_then(|(_, field)| field.span);
+    let last = out.fields.last().and_then(|(_, field)| field.span);
+    out.span = first.zip(last).map(|(first, last)| first.to(last));
+    Ok(out)
   }
 
-  /// Return the parsed field and the remainder of uninteresting lines.
-  fn eat_field<'a>(
+  /// Return the field name and the parsed field, leaving `lines` at the
+  /// next line that isn't part of it.
+  fn eat_field<I: Iterator<Item = Line<'s>>>(
     &self,
-    lines: &'a [&'a str],
-  ) -> eyre::Result<(&'a [&'a str], String, Field)> {
-    let (top_line, rest_lines) = lines
-      .split_first()
-      .ok_or_eyre("ran out of lines (impossible?)")?;
-    if top_line.starts_with(WHITESPACE) {
-      return Err(self.eyre(
-        top_line,
-        eyre!("field header must not start with whitespace"),
+    top_line: Line<'s>,
+    lines: &mut Peekable<I>,
+  ) -> Result<(Line<'s>, Field<'s>), SpannedError> {
+    if top_line.text.starts_with(WHITESPACE) {
+      return Err(self.error(
+        top_line.span(),
+        "field header must not start with whitespace",
       ));
     }
 
-    let (field_name, oneline_value) = self.parse_field_oneliner(top_line)?;
-    let (rest_lines, list_values) = self.eat_multiline_field_lines(rest_lines);
+    let (field_name, oneline_value) = self.parse_field_oneliner(&top_line)?;
+    let field_lines = self.eat_multiline_field_lines(lines);
+    let span = match field_lines.last() {
+      Some(last) => top_line.span().to(last.span()),
+      None => top_line.span(),
+    };
+    let list_values =
+      continuation_values(field_lines.iter().map(|line| line.text));
+    // Values only lose whitespace off the front and the end, so each
+    // one ends where its trimmed line does
+    let list_spans =
+      field_lines.iter().zip(&list_values).map(|(line, value)| {
+        let end = line.text.trim_end().len();
+        line.span_at(end - value.len(), value.len())
+      });
     Ok((
-      rest_lines,
       field_name,
       Field {
-        same_line_value: oneline_value,
+        same_line_value: oneline_value.map(|value| Cow::Borrowed(value.text)),
+        value_spans: oneline_value
+          .map(|value| value.span())
+          .into_iter()
+          .chain(list_spans)
+          .collect(),
         list_values,
+        span: Some(span),
       },
     ))
   }
-  ///
+
   /// Try to read the header line of a field.
   ///
   /// Return (`key`, `oneline_value`). If `oneline_value` is `None`,
   /// it is a multiline value.
   fn parse_field_oneliner(
     &self,
-    rest: &str,
-  ) -> eyre::Result<(String, Option<String>)> {
-    trace!("parsing field header at {}", self.rowcol_fmt(rest));
-    let (field_name, rest) = rest.split_once(':').ok_or_else(|| {
-      self.eyre(rest, eyre!("could not find `:` in field header line"))
+    line: &Line<'s>,
+  ) -> Result<(Line<'s>, Option<Line<'s>>), SpannedError> {
+    trace!("parsing field header at line {}", line.number);
+    let colon = line.text.find(':').ok_or_else(|| {
+      self.error(line.span(), "could not find `:` in field header line")
     })?;
-    let rest = rest.trim_start_matches(WHITESPACE);
+    let field_name = line.slice(0, colon);
+    let rest = line.text[colon + 1..].trim_start_matches(WHITESPACE);
     let oneline_value = if rest.is_empty() {
       None
     } else {
-      Some(rest.to_owned())
+      Some(line.slice(line.text.len() - rest.len(), line.text.len()))
     };
     trace!(
       "found field header {:?}: {:?} at {}",
-      &field_name,
-      &oneline_value,
-      self.rowcol_fmt(rest)
+      field_name.text,
+      oneline_value.map(|value| value.text),
+      field_name.span()
     );
-    Ok((field_name.to_owned(), oneline_value))
+    Ok((field_name, oneline_value))
   }
 
-  /// Consume lines until we find one that is not a valid value.
+  /// Consume lines until we find one that is not a continuation line.
   /// This function is infallible because it is legal to have
   /// zero valid lines. (Although annoying. Please don't do that.)
-  fn eat_multiline_field_lines<'a>(
+  ///
+  /// A line with only whitespace is not a continuation line: it ends the
+  /// stanza, same as an empty line.
+  fn eat_multiline_field_lines<I: Iterator<Item = Line<'s>>>(
     &self,
-    lines: &'a [&'a str],
-  ) -> (&'a [&'a str], Vec<String>) {
-    let out: Vec<_> = lines
-      .iter()
-      .map_while(|line| {
-        let parsed = self.parse_multiline_field_line(line);
-        // An error here just means this line was unsuccessful to parse.
-        // If error, don't abort, just stop iteration
-        parsed.ok()
+    lines: &mut Peekable<I>,
+  ) -> Vec<Line<'s>> {
+    std::iter::from_fn(|| {
+      lines.next_if(|line| {
+        line.text.starts_with(WHITESPACE) && !line.text.trim().is_empty()
       })
-      .collect();
-    // For each OK line, slice one off the input lines
-    let remainder_lines = &lines[out.len()..];
-    (remainder_lines, out)
-  }
-
-  fn parse_multiline_field_line(&self, line: &str) -> eyre::Result<String> {
-    if !line.starts_with(WHITESPACE) {
-      return Err(self.eyre(
-        line,
-        eyre!("multiline field lines must start with whitespace"),
-      ));
-    }
-    trace!("found multiline field line at {}", self.rowcol_fmt(line));
-    Ok(line.trim_matches(WHITESPACE).to_owned())
+    })
+    .inspect(|line| {
+      trace!("found multiline field line at line {}", line.number)
+    })
+    .collect()
   }
 }
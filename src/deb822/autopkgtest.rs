@@ -0,0 +1,162 @@
+//! Type-safe specialization of generic Debian control files
+//! to `debian/tests/control` syntax.
+//!
+//! https://salsa.debian.org/ci-team/autopkgtest/-/blob/master/doc/README.package-tests.rst
+
+use std::{path::Path, str::FromStr};
+
+use eyre::{Context, bail, eyre};
+
+use crate::{
+  deb822::{
+    Deb822File, Field, Stanza, extra_fields, field_relations, field_text,
+    insert_relations, insert_text, insert_words,
+  },
+  relation::Relations,
+};
+
+/// Where the test control file is, relative to the source package.
+pub const DEFAULT_PATH: &str = "./debian/tests/control";
+
+/// Specialization of [`Deb822File`] to `debian/tests/control`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestsControlFile {
+  pub tests: Vec<TestStanza>,
+}
+
+/// One stanza, declaring one or more tests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestStanza {
+  /// Names of test scripts in [`TestStanza::tests_directory`].
+  /// Exactly one of this and `test_command` is given.
+  pub tests: Vec<String>,
+  /// A shell command to run as the test.
+  pub test_command: Option<String>,
+  /// Relations the test needs installed. autopkgtest uses `@` (all the
+  /// binary packages) if this is empty.
+  pub depends: Relations,
+  pub restrictions: Vec<String>,
+  pub features: Vec<String>,
+  pub classes: Vec<String>,
+  pub architecture: Vec<String>,
+  /// Defaults to `debian/tests` if not given.
+  pub tests_directory: Option<String>,
+  /// Every other field, in file order.
+  pub extra: Stanza<'static>,
+}
+
+const TEST_FIELDS: &[&str] = &[
+  "Tests",
+  "Test-Command",
+  "Depends",
+  "Restrictions",
+  "Features",
+  "Classes",
+  "Architecture",
+  "Tests-Directory",
+];
+
+impl TestsControlFile {
+  /// Pull the tests out of the deb file. Every stanza is a test stanza.
+  pub fn new(deb: Deb822File) -> eyre::Result<Self> {
+    let tests = deb
+      .stanzas
+      .iter()
+      .enumerate()
+      .map(|(idx, stanza)| {
+        TestStanza::new(stanza)
+          .wrap_err_with(|| eyre!("in test stanza (stanza {})", idx + 1))
+      })
+      .collect::<eyre::Result<Vec<_>>>()?;
+    Ok(TestsControlFile { tests })
+  }
+
+  /// Read and parse the test control file at `path`.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).wrap_err_with(|| {
+      eyre!("could not read test control file at {}", path.display())
+    })?;
+
+    TestsControlFile::from_str(&source)
+      .wrap_err(eyre!("could not parse test control file"))
+  }
+
+  /// Turn this back into a generic [`Deb822File`].
+  pub fn to_deb822(&self) -> Deb822File<'static> {
+    Deb822File::new(self.tests.iter().map(TestStanza::to_stanza).collect())
+  }
+}
+
+impl TestStanza {
+  fn new(stanza: &Stanza) -> eyre::Result<Self> {
+    let tests = names(stanza.get("Tests"));
+    let test_command = stanza.get("Test-Command").map(field_text);
+    match (tests.is_empty(), &test_command) {
+      (true, None) => {
+        bail!("test stanza has neither `Tests` nor `Test-Command`")
+      }
+      (false, Some(_)) => {
+        bail!("test stanza has both `Tests` and `Test-Command`")
+      }
+      _ => {}
+    }
+
+    Ok(Self {
+      tests,
+      test_command,
+      depends: field_relations(stanza, "Depends")?,
+      restrictions: names(stanza.get("Restrictions")),
+      features: names(stanza.get("Features")),
+      classes: names(stanza.get("Classes")),
+      architecture: names(stanza.get("Architecture")),
+      tests_directory: stanza.get("Tests-Directory").map(field_text),
+      extra: extra_fields(stanza, TEST_FIELDS),
+    })
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    insert_words(&mut out, "Tests", &self.tests);
+    insert_text(&mut out, "Test-Command", self.test_command.as_deref());
+    insert_relations(&mut out, "Depends", &self.depends);
+    insert_words(&mut out, "Restrictions", &self.restrictions);
+    insert_words(&mut out, "Features", &self.features);
+    insert_words(&mut out, "Classes", &self.classes);
+    insert_words(&mut out, "Architecture", &self.architecture);
+    insert_text(&mut out, "Tests-Directory", self.tests_directory.as_deref());
+    for (name, field) in self.extra.iter() {
+      out.insert(name.to_owned(), field.clone());
+    }
+    out
+  }
+}
+
+/// These fields may be separated by commas, whitespace, or both.
+fn names(field: Option<&Field>) -> Vec<String> {
+  field
+    .into_iter()
+    .flat_map(Field::iter_lines)
+    .flat_map(|line| line.split([',', ' ', '\t']))
+    .filter(|name| !name.is_empty())
+    .map(str::to_owned)
+    .collect()
+}
+
+/// Write out [`TestsControlFile::to_deb822`].
+impl std::fmt::Display for TestsControlFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_deb822(), f)
+  }
+}
+
+impl FromStr for TestsControlFile {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let deb = Deb822File::from_str(s)?;
+    Self::new(deb)
+  }
+}
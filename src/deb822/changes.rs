@@ -0,0 +1,166 @@
+//! Type-safe specialization of generic Debian control files
+//! to upload control files (`.changes`).
+//!
+//! https://www.debian.org/doc/debian-policy/ch-controlfields#debian-changes-files-changes
+//!
+//! These are usually PGP-signed. Parsing skips the clearsign envelope
+//! and reads the signed body, but doesn't check the signature. Use
+//! [`SignedFile::verify`](super::signed::SignedFile::verify) to do that.
+
+use std::{path::Path, str::FromStr};
+
+use eyre::{Context, bail, eyre};
+
+use crate::deb822::{
+  Deb822File, Stanza,
+  checksums::{ChangesFileEntry, Checksum},
+  extra_fields, field_text, field_words, insert_block, insert_text,
+  insert_words, required_text,
+};
+
+/// Specialization of [`Deb822File`] to `.changes` files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangesFile {
+  pub format: Option<String>,
+  /// In RFC 2822 format.
+  pub date: Option<String>,
+  pub source: String,
+  pub binary: Vec<String>,
+  pub architecture: Vec<String>,
+  pub version: Option<String>,
+  pub distribution: Vec<String>,
+  pub urgency: Option<String>,
+  pub maintainer: Option<String>,
+  pub changed_by: Option<String>,
+  /// The first line is usually empty, then one line per binary package.
+  pub description: Option<String>,
+  /// Bug numbers.
+  pub closes: Vec<String>,
+  /// The changelog entries, with the first line usually empty.
+  pub changes: Option<String>,
+  pub checksums_sha1: Vec<Checksum>,
+  pub checksums_sha256: Vec<Checksum>,
+  pub files: Vec<ChangesFileEntry>,
+  /// Every other field, in file order.
+  pub extra: Stanza<'static>,
+}
+
+const CHANGES_FIELDS: &[&str] = &[
+  "Format",
+  "Date",
+  "Source",
+  "Binary",
+  "Architecture",
+  "Version",
+  "Distribution",
+  "Urgency",
+  "Maintainer",
+  "Changed-By",
+  "Description",
+  "Closes",
+  "Changes",
+  "Checksums-Sha1",
+  "Checksums-Sha256",
+  "Files",
+];
+
+impl ChangesFile {
+  /// Pull the relevant information out of the deb file, which must have
+  /// exactly one stanza.
+  pub fn new(deb: Deb822File) -> eyre::Result<Self> {
+    let [stanza] = deb.stanzas() else {
+      bail!(".changes file has {} stanzas, not 1", deb.stanzas().len());
+    };
+    Self::from_stanza(stanza)
+  }
+
+  pub fn from_stanza(stanza: &Stanza) -> eyre::Result<Self> {
+    Ok(Self {
+      format: stanza.get("Format").map(field_text),
+      date: stanza.get("Date").map(field_text),
+      source: required_text(stanza, "Source", ".changes")?,
+      binary: field_words(stanza.get("Binary")),
+      architecture: field_words(stanza.get("Architecture")),
+      version: stanza.get("Version").map(field_text),
+      distribution: field_words(stanza.get("Distribution")),
+      urgency: stanza.get("Urgency").map(field_text),
+      maintainer: stanza.get("Maintainer").map(field_text),
+      changed_by: stanza.get("Changed-By").map(field_text),
+      description: stanza.get("Description").map(field_text),
+      closes: field_words(stanza.get("Closes")),
+      changes: stanza.get("Changes").map(field_text),
+      checksums_sha1: Checksum::parse_list(stanza.get("Checksums-Sha1"))
+        .wrap_err("in Checksums-Sha1")?,
+      checksums_sha256: Checksum::parse_list(stanza.get("Checksums-Sha256"))
+        .wrap_err("in Checksums-Sha256")?,
+      files: ChangesFileEntry::parse_list(stanza.get("Files"))
+        .wrap_err("in Files")?,
+      extra: extra_fields(stanza, CHANGES_FIELDS),
+    })
+  }
+
+  /// Read and parse the `.changes` file at `path`.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).wrap_err_with(|| {
+      eyre!("could not read .changes file at {}", path.display())
+    })?;
+
+    ChangesFile::from_str(&source)
+      .wrap_err(eyre!("could not parse .changes file"))
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    insert_text(&mut out, "Format", self.format.as_deref());
+    insert_text(&mut out, "Date", self.date.as_deref());
+    insert_text(&mut out, "Source", Some(&self.source));
+    insert_words(&mut out, "Binary", &self.binary);
+    insert_words(&mut out, "Architecture", &self.architecture);
+    insert_text(&mut out, "Version", self.version.as_deref());
+    insert_words(&mut out, "Distribution", &self.distribution);
+    insert_text(&mut out, "Urgency", self.urgency.as_deref());
+    insert_text(&mut out, "Maintainer", self.maintainer.as_deref());
+    insert_text(&mut out, "Changed-By", self.changed_by.as_deref());
+    insert_block(&mut out, "Description", self.description.as_deref());
+    insert_words(&mut out, "Closes", &self.closes);
+    for (name, field) in self.extra.iter() {
+      out.insert(name.to_owned(), field.clone());
+    }
+    insert_block(&mut out, "Changes", self.changes.as_deref());
+    for (name, list) in [
+      ("Checksums-Sha1", &self.checksums_sha1),
+      ("Checksums-Sha256", &self.checksums_sha256),
+    ] {
+      if !list.is_empty() {
+        out.insert(name.to_owned(), Checksum::to_field(list));
+      }
+    }
+    if !self.files.is_empty() {
+      out.insert("Files".to_owned(), ChangesFileEntry::to_field(&self.files));
+    }
+    out
+  }
+
+  /// Turn this back into a generic [`Deb822File`].
+  pub fn to_deb822(&self) -> Deb822File<'static> {
+    Deb822File::new(vec![self.to_stanza()])
+  }
+}
+
+/// Write out [`ChangesFile::to_deb822`].
+impl std::fmt::Display for ChangesFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_deb822(), f)
+  }
+}
+
+impl FromStr for ChangesFile {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let deb = Deb822File::from_str(s)?;
+    Self::new(deb)
+  }
+}
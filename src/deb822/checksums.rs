@@ -0,0 +1,115 @@
+//! The checksum lists in `.dsc` and `.changes` files, like
+//! `Checksums-Sha256` and `Files`.
+//!
+//! Each continuation line lists one file.
+
+use std::borrow::Cow;
+
+use eyre::{Context, eyre};
+
+use crate::deb822::Field;
+
+/// One line of a checksum list: `<checksum> <size> <name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checksum {
+  /// Lowercase hex digest.
+  pub checksum: String,
+  pub size: u64,
+  pub name: String,
+}
+
+/// One line of the `Files` field of a `.changes` file:
+/// `<md5> <size> <section> <priority> <name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangesFileEntry {
+  pub md5: String,
+  pub size: u64,
+  pub section: String,
+  pub priority: String,
+  pub name: String,
+}
+
+impl Checksum {
+  /// Parse every line of a checksum list field.
+  pub fn parse_list(field: Option<&Field>) -> eyre::Result<Vec<Self>> {
+    list_lines(field)
+      .map(|line| {
+        let [checksum, size, name] = split_words(line)?;
+        Ok(Checksum {
+          checksum: checksum.to_owned(),
+          size: parse_size(size)?,
+          name: name.to_owned(),
+        })
+      })
+      .collect()
+  }
+
+  pub fn to_field(list: &[Self]) -> Field<'static> {
+    list_field(list.iter().map(|entry| {
+      format!("{} {} {}", &entry.checksum, entry.size, &entry.name)
+    }))
+  }
+}
+
+impl ChangesFileEntry {
+  /// Parse every line of a `.changes` `Files` field.
+  pub fn parse_list(field: Option<&Field>) -> eyre::Result<Vec<Self>> {
+    list_lines(field)
+      .map(|line| {
+        let [md5, size, section, priority, name] = split_words(line)?;
+        Ok(ChangesFileEntry {
+          md5: md5.to_owned(),
+          size: parse_size(size)?,
+          section: section.to_owned(),
+          priority: priority.to_owned(),
+          name: name.to_owned(),
+        })
+      })
+      .collect()
+  }
+
+  pub fn to_field(list: &[Self]) -> Field<'static> {
+    list_field(list.iter().map(|entry| {
+      format!(
+        "{} {} {} {} {}",
+        &entry.md5, entry.size, &entry.section, &entry.priority, &entry.name
+      )
+    }))
+  }
+}
+
+fn list_lines<'a>(field: Option<&'a Field>) -> impl Iterator<Item = &'a str> {
+  field
+    .into_iter()
+    .flat_map(Field::iter_lines)
+    .filter(|line| !line.trim().is_empty())
+}
+
+fn list_field(lines: impl Iterator<Item = String>) -> Field<'static> {
+  Field {
+    same_line_value: None,
+    list_values: lines.map(Cow::Owned).collect(),
+    span: None,
+    value_spans: Vec::new(),
+  }
+}
+
+fn split_words<const N: usize>(line: &str) -> eyre::Result<[&str; N]> {
+  let words: Vec<&str> = line.split_ascii_whitespace().collect();
+  words.try_into().map_err(|words: Vec<&str>| {
+    eyre!(
+      "expected {} words in checksum line {:?}, found {}",
+      N,
+      line,
+      words.len()
+    )
+  })
+}
+
+fn parse_size(size: &str) -> eyre::Result<u64> {
+  size
+    .parse()
+    .wrap_err_with(|| eyre!("invalid file size {:?}", size))
+}
@@ -0,0 +1,316 @@
+//! Type-safe specialization of generic Debian control files
+//! to `debian/control` syntax.
+//!
+//! https://www.debian.org/doc/debian-policy/ch-controlfields#debian-source-package-template-control-files-debian-control
+
+use std::{path::Path, str::FromStr};
+
+use eyre::{Context, eyre};
+use log::info;
+
+use crate::{
+  deb822::{
+    Deb822File, Stanza, extra_fields, field_list, field_relations, field_text,
+    field_words, insert_list, insert_relations, insert_text, insert_words,
+    required_text,
+  },
+  relation::Relations,
+};
+
+/// Where the control file is, relative to the source package.
+pub const DEFAULT_PATH: &str = "./debian/control";
+
+/// Specialization of [`Deb822File`] to `debian/control`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlFile {
+  pub source: SourceStanza,
+  /// The binary package stanzas, in file order.
+  pub binaries: Vec<BinaryStanza>,
+}
+
+/// The first stanza, about the source package.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceStanza {
+  pub source: String,
+  pub section: Option<String>,
+  pub priority: Option<String>,
+  pub maintainer: Option<String>,
+  pub uploaders: Vec<String>,
+  pub standards_version: Option<String>,
+  pub homepage: Option<String>,
+  pub vcs_browser: Option<String>,
+  pub vcs_git: Option<String>,
+  pub rules_requires_root: Option<String>,
+  pub build_depends: Relations,
+  pub build_depends_indep: Relations,
+  pub build_depends_arch: Relations,
+  pub build_conflicts: Relations,
+  pub build_conflicts_indep: Relations,
+  pub build_conflicts_arch: Relations,
+  /// Every other field, in file order.
+  pub extra: Stanza<'static>,
+}
+
+/// A stanza about one binary package.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinaryStanza {
+  pub package: String,
+  /// Architecture names or wildcards, like `any` or `linux-any`.
+  pub architecture: Vec<String>,
+  pub section: Option<String>,
+  pub priority: Option<String>,
+  pub multi_arch: Option<String>,
+  pub essential: Option<String>,
+  pub depends: Relations,
+  pub pre_depends: Relations,
+  pub recommends: Relations,
+  pub suggests: Relations,
+  pub enhances: Relations,
+  pub breaks: Relations,
+  pub conflicts: Relations,
+  pub provides: Relations,
+  pub replaces: Relations,
+  pub built_using: Relations,
+  /// The synopsis, then the long description lines.
+  pub description: Option<String>,
+  /// Every other field, in file order.
+  pub extra: Stanza<'static>,
+}
+
+const SOURCE_FIELDS: &[&str] = &[
+  "Source",
+  "Section",
+  "Priority",
+  "Maintainer",
+  "Uploaders",
+  "Standards-Version",
+  "Homepage",
+  "Vcs-Browser",
+  "Vcs-Git",
+  "Rules-Requires-Root",
+  "Build-Depends",
+  "Build-Depends-Indep",
+  "Build-Depends-Arch",
+  "Build-Conflicts",
+  "Build-Conflicts-Indep",
+  "Build-Conflicts-Arch",
+];
+
+const BINARY_FIELDS: &[&str] = &[
+  "Package",
+  "Architecture",
+  "Section",
+  "Priority",
+  "Multi-Arch",
+  "Essential",
+  "Depends",
+  "Pre-Depends",
+  "Recommends",
+  "Suggests",
+  "Enhances",
+  "Breaks",
+  "Conflicts",
+  "Provides",
+  "Replaces",
+  "Built-Using",
+  "Description",
+];
+
+impl ControlFile {
+  /// Pull the relevant information out of the deb file.
+  ///
+  /// The first stanza is the source stanza, and every other one is a
+  /// binary package.
+  pub fn new(deb: Deb822File) -> eyre::Result<Self> {
+    let mut stanzas = deb.stanzas.iter();
+    let source = stanzas
+      .next()
+      .ok_or_else(|| eyre!("control file has no stanzas"))
+      .and_then(SourceStanza::new)?;
+    let binaries = stanzas
+      .enumerate()
+      .map(|(idx, stanza)| {
+        BinaryStanza::new(stanza)
+          .wrap_err_with(|| eyre!("in binary stanza (stanza {})", idx + 2))
+      })
+      .collect::<eyre::Result<Vec<_>>>()?;
+
+    info!(
+      "specialized ControlFile for source {:?} with {} binary packages",
+      &source.source,
+      binaries.len()
+    );
+    Ok(ControlFile { source, binaries })
+  }
+
+  /// Read and parse the control file at `path`.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).wrap_err_with(|| {
+      eyre!("could not read control file at {}", path.display())
+    })?;
+
+    ControlFile::from_str(&source)
+      .wrap_err(eyre!("could not parse control file"))
+  }
+
+  /// Find the binary package called `name`.
+  pub fn binary(&self, name: &str) -> Option<&BinaryStanza> {
+    self.binaries.iter().find(|binary| binary.package == name)
+  }
+
+  /// Turn this back into a generic [`Deb822File`].
+  pub fn to_deb822(&self) -> Deb822File<'static> {
+    let stanzas = std::iter::once(self.source.to_stanza())
+      .chain(self.binaries.iter().map(BinaryStanza::to_stanza))
+      .collect();
+    Deb822File::new(stanzas)
+  }
+}
+
+impl SourceStanza {
+  fn new(stanza: &Stanza) -> eyre::Result<Self> {
+    Ok(Self {
+      source: required_text(stanza, "Source", "source")?,
+      section: stanza.get("Section").map(field_text),
+      priority: stanza.get("Priority").map(field_text),
+      maintainer: stanza.get("Maintainer").map(field_text),
+      uploaders: field_list(stanza.get("Uploaders")),
+      standards_version: stanza.get("Standards-Version").map(field_text),
+      homepage: stanza.get("Homepage").map(field_text),
+      vcs_browser: stanza.get("Vcs-Browser").map(field_text),
+      vcs_git: stanza.get("Vcs-Git").map(field_text),
+      rules_requires_root: stanza.get("Rules-Requires-Root").map(field_text),
+      build_depends: field_relations(stanza, "Build-Depends")?,
+      build_depends_indep: field_relations(stanza, "Build-Depends-Indep")?,
+      build_depends_arch: field_relations(stanza, "Build-Depends-Arch")?,
+      build_conflicts: field_relations(stanza, "Build-Conflicts")?,
+      build_conflicts_indep: field_relations(stanza, "Build-Conflicts-Indep")?,
+      build_conflicts_arch: field_relations(stanza, "Build-Conflicts-Arch")?,
+      extra: extra_fields(stanza, SOURCE_FIELDS),
+    })
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    insert_text(&mut out, "Source", Some(&self.source));
+    insert_text(&mut out, "Section", self.section.as_deref());
+    insert_text(&mut out, "Priority", self.priority.as_deref());
+    insert_text(&mut out, "Maintainer", self.maintainer.as_deref());
+    insert_list(&mut out, "Uploaders", &self.uploaders);
+    insert_relations(&mut out, "Build-Depends", &self.build_depends);
+    insert_relations(
+      &mut out,
+      "Build-Depends-Indep",
+      &self.build_depends_indep,
+    );
+    insert_relations(&mut out, "Build-Depends-Arch", &self.build_depends_arch);
+    insert_relations(&mut out, "Build-Conflicts", &self.build_conflicts);
+    insert_relations(
+      &mut out,
+      "Build-Conflicts-Indep",
+      &self.build_conflicts_indep,
+    );
+    insert_relations(
+      &mut out,
+      "Build-Conflicts-Arch",
+      &self.build_conflicts_arch,
+    );
+    insert_text(
+      &mut out,
+      "Standards-Version",
+      self.standards_version.as_deref(),
+    );
+    insert_text(&mut out, "Homepage", self.homepage.as_deref());
+    insert_text(&mut out, "Vcs-Browser", self.vcs_browser.as_deref());
+    insert_text(&mut out, "Vcs-Git", self.vcs_git.as_deref());
+    insert_text(
+      &mut out,
+      "Rules-Requires-Root",
+      self.rules_requires_root.as_deref(),
+    );
+    for (name, field) in self.extra.iter() {
+      out.insert(name.to_owned(), field.clone());
+    }
+    out
+  }
+}
+
+impl BinaryStanza {
+  fn new(stanza: &Stanza) -> eyre::Result<Self> {
+    Ok(Self {
+      package: required_text(stanza, "Package", "binary")?,
+      architecture: field_words(stanza.get("Architecture")),
+      section: stanza.get("Section").map(field_text),
+      priority: stanza.get("Priority").map(field_text),
+      multi_arch: stanza.get("Multi-Arch").map(field_text),
+      essential: stanza.get("Essential").map(field_text),
+      depends: field_relations(stanza, "Depends")?,
+      pre_depends: field_relations(stanza, "Pre-Depends")?,
+      recommends: field_relations(stanza, "Recommends")?,
+      suggests: field_relations(stanza, "Suggests")?,
+      enhances: field_relations(stanza, "Enhances")?,
+      breaks: field_relations(stanza, "Breaks")?,
+      conflicts: field_relations(stanza, "Conflicts")?,
+      provides: field_relations(stanza, "Provides")?,
+      replaces: field_relations(stanza, "Replaces")?,
+      built_using: field_relations(stanza, "Built-Using")?,
+      description: stanza.get("Description").map(field_text),
+      extra: extra_fields(stanza, BINARY_FIELDS),
+    })
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    insert_text(&mut out, "Package", Some(&self.package));
+    insert_words(&mut out, "Architecture", &self.architecture);
+    insert_text(&mut out, "Section", self.section.as_deref());
+    insert_text(&mut out, "Priority", self.priority.as_deref());
+    insert_text(&mut out, "Multi-Arch", self.multi_arch.as_deref());
+    insert_text(&mut out, "Essential", self.essential.as_deref());
+    for (name, list) in [
+      ("Pre-Depends", &self.pre_depends),
+      ("Depends", &self.depends),
+      ("Recommends", &self.recommends),
+      ("Suggests", &self.suggests),
+      ("Enhances", &self.enhances),
+      ("Breaks", &self.breaks),
+      ("Conflicts", &self.conflicts),
+      ("Provides", &self.provides),
+      ("Replaces", &self.replaces),
+      ("Built-Using", &self.built_using),
+    ] {
+      insert_relations(&mut out, name, list);
+    }
+    for (name, field) in self.extra.iter() {
+      out.insert(name.to_owned(), field.clone());
+    }
+    insert_text(&mut out, "Description", self.description.as_deref());
+    out
+  }
+}
+
+/// Write out [`ControlFile::to_deb822`].
+impl std::fmt::Display for ControlFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_deb822(), f)
+  }
+}
+
+impl std::fmt::Display for BinaryStanza {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_stanza(), f)
+  }
+}
+
+impl FromStr for ControlFile {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let deb = Deb822File::from_str(s)?;
+    Self::new(deb)
+  }
+}
@@ -3,69 +3,391 @@
 //!
 //! https://www.debian.org/doc/packaging-manuals/copyright-format/1.0
 
-use std::{path::Path, str::FromStr};
+use std::{borrow::Cow, path::Path, str::FromStr};
 
 use eyre::{Context, eyre};
-use log::info;
+use log::{info, warn};
 
-use crate::{deb822::Deb822File, glob::Glob};
+use crate::{
+  deb822::{
+    Deb822File, Field, FieldKind, Stanza, field_lines, field_text,
+    insert_lines, insert_text,
+  },
+  filter::PathFilter,
+  glob::{Glob, set::GlobSet},
+  license::LicenseExpr,
+  strip::EntryInfo,
+};
 
-/// Specialization of [`Deb822File`] that throws away most of the information
-/// except for all the file exclusions.
-#[derive(Clone, Debug)]
+/// Where the copyright file is, relative to the source package.
+pub const DEFAULT_PATH: &str = "./debian/copyright";
+
+/// The `Format` of the one version of the spec there is.
+pub const FORMAT_URL: &str =
+  "https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/";
+
+/// Specialization of [`Deb822File`] to the machine-readable copyright format.
+///
+/// Parsing is lenient: fields the spec says are required are `Option`s
+/// here, so that half-finished files can still be used to strip tarballs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CopyrightFile {
-  excludes: Vec<Glob>,
+  pub header: Header,
+  /// The `Files` paragraphs, in the order they appear in the file.
+  pub files: Vec<FilesParagraph>,
+  /// The standalone `License` paragraphs.
+  pub licenses: Vec<LicenseParagraph>,
+  /// `Files-Excluded` globs. These are collected from every paragraph,
+  /// not just the header, to be forgiving.
+  #[cfg_attr(feature = "serde", serde(rename = "files_excluded", default))]
+  excludes: GlobSet,
+  /// Globs that override `excludes`. Like those, they are collected
+  /// from every paragraph.
+  ///
+  /// This is an extension to the copyright format, named to match
+  /// `Files-Excluded`. It saves listing every sibling of the one
+  /// directory you want to keep.
+  #[cfg_attr(feature = "serde", serde(rename = "files_included", default))]
+  includes: GlobSet,
+}
+
+/// The first paragraph of the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(default)
+)]
+pub struct Header {
+  /// URI of the format specification, usually [`FORMAT_URL`].
+  pub format: Option<String>,
+  pub upstream_name: Option<String>,
+  /// One contact per line.
+  pub upstream_contact: Vec<String>,
+  pub source: Option<String>,
+  pub disclaimer: Option<String>,
+  pub comment: Option<String>,
+  /// The license of the work as a whole.
+  pub license: Option<License>,
+  /// One copyright statement per line.
+  pub copyright: Vec<String>,
+}
+
+/// A paragraph saying what copyright and license some files are under.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilesParagraph {
+  pub files: Vec<Glob>,
+  /// One copyright statement per line.
+  pub copyright: Vec<String>,
+  pub license: Option<License>,
+  pub comment: Option<String>,
+}
+
+/// A paragraph giving the text of a license used elsewhere in the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LicenseParagraph {
+  pub license: License,
+  pub comment: Option<String>,
+}
+
+/// The value of a `License` field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct License {
+  /// The first line, which is the short name of the license
+  /// (or an expression of several, like `GPL-2+ or MIT`).
+  pub name: String,
+  /// The rest of the lines, which are the full license text, if given.
+  /// If this is empty, the text should be in a standalone
+  /// [`LicenseParagraph`].
+  pub text: Vec<String>,
 }
 
 impl CopyrightFile {
   /// Pull the relevant information out of the deb file.
   ///
-  /// At the moment, because this program is meant for excluding
-  /// files and nothing else, stanzas without any copyright
-  /// information are not put into `self`.
+  /// The first stanza is the header, unless it has a `Files` field, in
+  /// which case the file has no header. After that, stanzas with a `Files`
+  /// field are [`FilesParagraph`]s, and stanzas with only a `License` are
+  /// [`LicenseParagraph`]s. Anything else is skipped with a warning.
   pub fn new(deb: Deb822File) -> eyre::Result<Self> {
-    let excludes: Result<Vec<_>, _> = deb
-      .stanzas
-      .iter()
-      .filter_map(|stanza| stanza.fields.get("Files-Excluded"))
-      .flat_map(|fex| fex.iter_lines())
-      .flat_map(|line| line.split_ascii_whitespace())
-      .filter_map(|glob_str| {
-        let glob = Glob::from_str(&glob_str);
-        match glob {
-          Ok(glob) => {
-            if !glob.is_empty() {
-              Some(Ok(glob))
-            } else {
-              None
-            }
-          }
-          ono @ Err(..) => Some(ono.wrap_err_with(|| {
-            eyre!("while parsing glob string {:?}", &glob_str)
-          })),
-        }
-      })
-      .collect();
-    let excludes = excludes?;
+    let excludes = GlobSet::new(Self::collect_globs(&deb, "Files-Excluded")?);
+    let includes = GlobSet::new(Self::collect_globs(&deb, "Files-Included")?);
+
+    let mut stanzas = deb.stanzas.iter().enumerate().peekable();
+    let header = match stanzas.next_if(|(_, st)| st.get("Files").is_none()) {
+      Some((_, stanza)) => Header::new(stanza),
+      None => {
+        warn!("first stanza is a Files paragraph, so there is no header");
+        Header::default()
+      }
+    };
+    let mut files = Vec::new();
+    let mut licenses = Vec::new();
+    for (idx, stanza) in stanzas {
+      if stanza.get("Files").is_some() {
+        files.push(FilesParagraph::new(stanza).wrap_err_with(|| {
+          eyre!("in Files paragraph (stanza {})", idx + 1)
+        })?);
+      } else if let Some(license) = stanza.get("License") {
+        licenses.push(LicenseParagraph {
+          license: License::new(license),
+          comment: stanza.get("Comment").map(field_text),
+        });
+      } else {
+        warn!(
+          "stanza {} is neither a Files nor a License paragraph, skipping it",
+          idx + 1
+        );
+      }
+    }
+
     info!(
-      "specialized CopyrightFile, {} stanzas turned into {} globs",
+      "specialized CopyrightFile, {} stanzas turned into {} Files and {} \
+       License paragraphs, with {} exclude and {} include globs",
       deb.stanzas.len(),
-      excludes.len()
+      files.len(),
+      licenses.len(),
+      excludes.len(),
+      includes.len()
     );
-    Ok(CopyrightFile { excludes })
+    Ok(CopyrightFile {
+      header,
+      files,
+      licenses,
+      excludes,
+      includes,
+    })
+  }
+
+  /// Read and parse the copyright file at `path`.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+    let path = path.as_ref();
+    let copyright_file = std::fs::read_to_string(path).wrap_err_with(|| {
+      eyre!("could not read copyright file at {}", path.display())
+    })?;
+
+    CopyrightFile::from_str(&copyright_file)
+      .wrap_err(eyre!("could not parse copyright file"))
+  }
+
+  /// Gather the whitespace-separated globs out of every field called
+  /// `field_name` in the file.
+  fn collect_globs(
+    deb: &Deb822File,
+    field_name: &str,
+  ) -> eyre::Result<Vec<Glob>> {
+    let globs: eyre::Result<Vec<Vec<Glob>>> = deb
+      .stanzas
+      .iter()
+      .flat_map(|stanza| stanza.get_all(field_name))
+      .map(|field| parse_globs(field, field_name))
+      .collect();
+    Ok(globs?.into_iter().flatten().collect())
+  }
+
+  /// The `Files-Excluded` globs.
+  pub fn excludes(&self) -> &[Glob] {
+    self.excludes.globs()
+  }
+
+  /// The `Files-Included` globs.
+  pub fn includes(&self) -> &[Glob] {
+    self.includes.globs()
+  }
+
+  /// Find the standalone license paragraph with this short name.
+  pub fn license_text(&self, name: &str) -> Option<&LicenseParagraph> {
+    self
+      .licenses
+      .iter()
+      .find(|paragraph| paragraph.license.name == name)
+  }
+
+  /// Find the `Files` paragraph that says what copyright and license
+  /// `path` is under.
+  ///
+  /// Following the spec, if several paragraphs match, the last one wins.
+  pub fn lookup<P: AsRef<Path>>(&self, p: P) -> Option<&FilesParagraph> {
+    let path = p.as_ref();
+    self
+      .files
+      .iter()
+      .rev()
+      .find(|paragraph| paragraph.matches(path))
+  }
+
+  /// Turn this back into a generic [`Deb822File`].
+  ///
+  /// `Files-Excluded` and `Files-Included` are written into the header,
+  /// even if they were somewhere else in the original file.
+  pub fn to_deb822(&self) -> Deb822File<'static> {
+    let mut header = self.header.to_stanza();
+    for (name, globs) in [
+      ("Files-Excluded", self.excludes()),
+      ("Files-Included", self.includes()),
+    ] {
+      if !globs.is_empty() {
+        header.insert(name.to_owned(), globs_field(globs));
+      }
+    }
+
+    // A file with no header stays that way
+    let stanzas = Some(header)
+      .filter(|header| !header.is_empty())
+      .into_iter()
+      .chain(self.files.iter().map(FilesParagraph::to_stanza))
+      .chain(self.licenses.iter().map(LicenseParagraph::to_stanza))
+      .collect();
+    Deb822File::new(stanzas)
   }
 
   /// Check if the given path is excluded.
   ///
-  /// Note that this plays a little bit fast-and-loose with
-  /// non-UTF8 paths. It first converts the path using
-  /// [`Path::to_string_lossy`], which usually is good enough.
+  /// A path is excluded if it matches something in `Files-Excluded`,
+  /// and nothing in `Files-Included`.
   ///
-  /// If it becomes a problem I'll fix it.
+  /// Paths that aren't UTF-8 are matched byte for byte, see
+  /// [`Glob::matches_bytes`].
   pub fn is_path_excluded<P: AsRef<Path>>(&self, p: P) -> bool {
     let p = p.as_ref();
-    let path_str = p.to_string_lossy();
-    self.excludes.iter().any(|glob| glob.matches(&*path_str))
+    self.excludes.is_match(p) && !self.includes.is_match(p)
+  }
+}
+
+impl Header {
+  fn new(stanza: &Stanza) -> Self {
+    Self {
+      format: stanza
+        .get("Format")
+        .map(|field| field.value(FieldKind::Simple)),
+      upstream_name: stanza
+        .get("Upstream-Name")
+        .map(|field| field.value(FieldKind::Simple)),
+      upstream_contact: field_lines(stanza.get("Upstream-Contact")),
+      source: stanza.get("Source").map(field_text),
+      disclaimer: stanza.get("Disclaimer").map(field_text),
+      comment: stanza.get("Comment").map(field_text),
+      license: stanza.get("License").map(License::new),
+      copyright: field_lines(stanza.get("Copyright")),
+    }
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    insert_text(&mut out, "Format", self.format.as_deref());
+    insert_text(&mut out, "Upstream-Name", self.upstream_name.as_deref());
+    insert_lines(&mut out, "Upstream-Contact", &self.upstream_contact);
+    insert_text(&mut out, "Source", self.source.as_deref());
+    insert_text(&mut out, "Disclaimer", self.disclaimer.as_deref());
+    insert_text(&mut out, "Comment", self.comment.as_deref());
+    if let Some(license) = &self.license {
+      out.insert("License".to_owned(), license.to_field());
+    }
+    insert_lines(&mut out, "Copyright", &self.copyright);
+    out
+  }
+}
+
+impl FilesParagraph {
+  /// Check if any of the `Files` globs match `path`.
+  pub fn matches<P: AsRef<Path>>(&self, path: P) -> bool {
+    let path = path.as_ref();
+    self.files.iter().any(|glob| glob.matches(path))
+  }
+
+  fn new(stanza: &Stanza) -> eyre::Result<Self> {
+    let files = match stanza.get("Files") {
+      Some(field) => parse_globs(field, "Files")?,
+      None => Vec::new(),
+    };
+    Ok(Self {
+      files,
+      copyright: field_lines(stanza.get("Copyright")),
+      license: stanza.get("License").map(License::new),
+      comment: stanza.get("Comment").map(field_text),
+    })
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    out.insert("Files".to_owned(), globs_field(&self.files));
+    insert_lines(&mut out, "Copyright", &self.copyright);
+    if let Some(license) = &self.license {
+      out.insert("License".to_owned(), license.to_field());
+    }
+    insert_text(&mut out, "Comment", self.comment.as_deref());
+    out
+  }
+}
+
+impl LicenseParagraph {
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    out.insert("License".to_owned(), self.license.to_field());
+    insert_text(&mut out, "Comment", self.comment.as_deref());
+    out
+  }
+}
+
+impl License {
+  /// Parse the short name as a license expression.
+  pub fn expr(&self) -> eyre::Result<LicenseExpr> {
+    LicenseExpr::from_str(&self.name)
+  }
+
+  fn new(field: &Field) -> Self {
+    Self {
+      name: field
+        .same_line_value
+        .as_deref()
+        .unwrap_or_default()
+        .to_owned(),
+      text: field
+        .list_values
+        .iter()
+        .map(|line| line.to_string())
+        .collect(),
+    }
+  }
+
+  fn to_field(&self) -> Field<'static> {
+    Field {
+      same_line_value: Some(self.name.clone())
+        .filter(|name| !name.is_empty())
+        .map(Cow::Owned),
+      list_values: self.text.iter().cloned().map(Cow::Owned).collect(),
+      span: None,
+      value_spans: Vec::new(),
+    }
+  }
+}
+
+/// Write out [`CopyrightFile::to_deb822`].
+impl std::fmt::Display for CopyrightFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_deb822(), f)
+  }
+}
+
+impl std::fmt::Display for FilesParagraph {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_stanza(), f)
+  }
+}
+
+impl std::fmt::Display for LicenseParagraph {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_stanza(), f)
+  }
+}
+
+impl PathFilter for CopyrightFile {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    self.is_path_excluded(entry.path)
   }
 }
 
@@ -77,3 +399,49 @@ impl FromStr for CopyrightFile {
     Self::new(deb)
   }
 }
+
+/// One glob goes on the same line, several go one per line.
+fn globs_field(globs: &[Glob]) -> Field<'static> {
+  let mut globs = globs.iter().map(|glob| Cow::Owned(glob.to_string()));
+  if globs.len() == 1 {
+    Field {
+      same_line_value: globs.next(),
+      list_values: Vec::new(),
+      span: None,
+      value_spans: Vec::new(),
+    }
+  } else {
+    Field {
+      same_line_value: None,
+      list_values: globs.collect(),
+      span: None,
+      value_spans: Vec::new(),
+    }
+  }
+}
+
+fn parse_globs(field: &Field, field_name: &str) -> eyre::Result<Vec<Glob>> {
+  field
+    .iter_lines()
+    .flat_map(|line| line.split_ascii_whitespace())
+    .filter_map(|glob_str| {
+      let glob = Glob::from_str(glob_str);
+      match glob {
+        Ok(glob) => {
+          if !glob.is_empty() {
+            Some(Ok(glob))
+          } else {
+            None
+          }
+        }
+        ono @ Err(..) => Some(ono.wrap_err_with(|| {
+          eyre!(
+            "while parsing glob string {:?} in {}",
+            &glob_str,
+            field_name
+          )
+        })),
+      }
+    })
+    .collect()
+}
@@ -0,0 +1,216 @@
+//! Type-safe specialization of generic Debian control files
+//! to source package control files (`.dsc`).
+//!
+//! https://www.debian.org/doc/debian-policy/ch-controlfields#debian-source-package-control-files-dsc
+//!
+//! These are usually PGP-signed. [`Deb822File::parse`] takes the
+//! clearsign envelope off by itself, without checking the signature; use
+//! [`SignedFile::verify`](super::signed::SignedFile::verify) for that.
+
+use std::{borrow::Cow, path::Path, str::FromStr};
+
+use eyre::{Context, bail, eyre};
+
+use crate::{
+  deb822::{
+    Deb822File, Field, Stanza, checksums::Checksum, extra_fields, field_lines,
+    field_list, field_relations, field_text, field_words, insert_list,
+    insert_text, insert_words, required_text,
+  },
+  relation::Relations,
+};
+
+/// Specialization of [`Deb822File`] to `.dsc` files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DscFile {
+  pub format: Option<String>,
+  pub source: String,
+  pub binary: Vec<String>,
+  pub architecture: Vec<String>,
+  pub version: Option<String>,
+  pub maintainer: Option<String>,
+  pub uploaders: Vec<String>,
+  pub homepage: Option<String>,
+  pub standards_version: Option<String>,
+  pub vcs_browser: Option<String>,
+  pub vcs_git: Option<String>,
+  pub testsuite: Vec<String>,
+  pub build_depends: Relations,
+  pub build_depends_indep: Relations,
+  pub build_depends_arch: Relations,
+  pub build_conflicts: Relations,
+  pub build_conflicts_indep: Relations,
+  pub build_conflicts_arch: Relations,
+  /// One binary package per line, like `foo deb utils optional arch=any`.
+  pub package_list: Vec<String>,
+  pub checksums_sha1: Vec<Checksum>,
+  pub checksums_sha256: Vec<Checksum>,
+  /// MD5 checksums.
+  pub files: Vec<Checksum>,
+  /// Every other field, in file order.
+  pub extra: Stanza<'static>,
+}
+
+const DSC_FIELDS: &[&str] = &[
+  "Format",
+  "Source",
+  "Binary",
+  "Architecture",
+  "Version",
+  "Maintainer",
+  "Uploaders",
+  "Homepage",
+  "Standards-Version",
+  "Vcs-Browser",
+  "Vcs-Git",
+  "Testsuite",
+  "Build-Depends",
+  "Build-Depends-Indep",
+  "Build-Depends-Arch",
+  "Build-Conflicts",
+  "Build-Conflicts-Indep",
+  "Build-Conflicts-Arch",
+  "Package-List",
+  "Checksums-Sha1",
+  "Checksums-Sha256",
+  "Files",
+];
+
+impl DscFile {
+  /// Pull the relevant information out of the deb file, which must have
+  /// exactly one stanza.
+  pub fn new(deb: Deb822File) -> eyre::Result<Self> {
+    let [stanza] = deb.stanzas() else {
+      bail!(".dsc file has {} stanzas, not 1", deb.stanzas().len());
+    };
+    Self::from_stanza(stanza)
+  }
+
+  pub fn from_stanza(stanza: &Stanza) -> eyre::Result<Self> {
+    Ok(Self {
+      format: stanza.get("Format").map(field_text),
+      source: required_text(stanza, "Source", ".dsc")?,
+      binary: field_list(stanza.get("Binary")),
+      architecture: field_words(stanza.get("Architecture")),
+      version: stanza.get("Version").map(field_text),
+      maintainer: stanza.get("Maintainer").map(field_text),
+      uploaders: field_list(stanza.get("Uploaders")),
+      homepage: stanza.get("Homepage").map(field_text),
+      standards_version: stanza.get("Standards-Version").map(field_text),
+      vcs_browser: stanza.get("Vcs-Browser").map(field_text),
+      vcs_git: stanza.get("Vcs-Git").map(field_text),
+      testsuite: field_list(stanza.get("Testsuite")),
+      build_depends: field_relations(stanza, "Build-Depends")?,
+      build_depends_indep: field_relations(stanza, "Build-Depends-Indep")?,
+      build_depends_arch: field_relations(stanza, "Build-Depends-Arch")?,
+      build_conflicts: field_relations(stanza, "Build-Conflicts")?,
+      build_conflicts_indep: field_relations(stanza, "Build-Conflicts-Indep")?,
+      build_conflicts_arch: field_relations(stanza, "Build-Conflicts-Arch")?,
+      package_list: field_lines(stanza.get("Package-List")),
+      checksums_sha1: Checksum::parse_list(stanza.get("Checksums-Sha1"))
+        .wrap_err("in Checksums-Sha1")?,
+      checksums_sha256: Checksum::parse_list(stanza.get("Checksums-Sha256"))
+        .wrap_err("in Checksums-Sha256")?,
+      files: Checksum::parse_list(stanza.get("Files")).wrap_err("in Files")?,
+      extra: extra_fields(stanza, DSC_FIELDS),
+    })
+  }
+
+  /// Read and parse the `.dsc` file at `path`.
+  pub fn from_path<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).wrap_err_with(|| {
+      eyre!("could not read .dsc file at {}", path.display())
+    })?;
+
+    DscFile::from_str(&source).wrap_err(eyre!("could not parse .dsc file"))
+  }
+
+  pub fn to_stanza(&self) -> Stanza<'static> {
+    let mut out = Stanza::new();
+    insert_text(&mut out, "Format", self.format.as_deref());
+    insert_text(&mut out, "Source", Some(&self.source));
+    if !self.binary.is_empty() {
+      insert_text(&mut out, "Binary", Some(&self.binary.join(", ")));
+    }
+    insert_words(&mut out, "Architecture", &self.architecture);
+    insert_text(&mut out, "Version", self.version.as_deref());
+    insert_text(&mut out, "Maintainer", self.maintainer.as_deref());
+    insert_list(&mut out, "Uploaders", &self.uploaders);
+    insert_text(&mut out, "Homepage", self.homepage.as_deref());
+    insert_text(
+      &mut out,
+      "Standards-Version",
+      self.standards_version.as_deref(),
+    );
+    insert_text(&mut out, "Vcs-Browser", self.vcs_browser.as_deref());
+    insert_text(&mut out, "Vcs-Git", self.vcs_git.as_deref());
+    if !self.testsuite.is_empty() {
+      insert_text(&mut out, "Testsuite", Some(&self.testsuite.join(", ")));
+    }
+    for (name, list) in [
+      ("Build-Depends", &self.build_depends),
+      ("Build-Depends-Indep", &self.build_depends_indep),
+      ("Build-Depends-Arch", &self.build_depends_arch),
+      ("Build-Conflicts", &self.build_conflicts),
+      ("Build-Conflicts-Indep", &self.build_conflicts_indep),
+      ("Build-Conflicts-Arch", &self.build_conflicts_arch),
+    ] {
+      if !list.is_empty() {
+        // dpkg-source writes these on one line
+        insert_text(&mut out, name, Some(&list.to_string()));
+      }
+    }
+    for (name, field) in self.extra.iter() {
+      out.insert(name.to_owned(), field.clone());
+    }
+    if !self.package_list.is_empty() {
+      out.insert(
+        "Package-List".to_owned(),
+        Field {
+          same_line_value: None,
+          list_values: self
+            .package_list
+            .iter()
+            .cloned()
+            .map(Cow::Owned)
+            .collect(),
+          span: None,
+          value_spans: Vec::new(),
+        },
+      );
+    }
+    for (name, list) in [
+      ("Checksums-Sha1", &self.checksums_sha1),
+      ("Checksums-Sha256", &self.checksums_sha256),
+      ("Files", &self.files),
+    ] {
+      if !list.is_empty() {
+        out.insert(name.to_owned(), Checksum::to_field(list));
+      }
+    }
+    out
+  }
+
+  /// Turn this back into a generic [`Deb822File`].
+  pub fn to_deb822(&self) -> Deb822File<'static> {
+    Deb822File::new(vec![self.to_stanza()])
+  }
+}
+
+/// Write out [`DscFile::to_deb822`].
+impl std::fmt::Display for DscFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    std::fmt::Display::fmt(&self.to_deb822(), f)
+  }
+}
+
+impl FromStr for DscFile {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let deb = Deb822File::from_str(s)?;
+    Self::new(deb)
+  }
+}
@@ -0,0 +1,288 @@
+//! Lossless representation of Debian control files.
+//!
+//! [`Deb822File`] only keeps the data. This keeps everything else too:
+//! field order, comments, blank lines, and all the original whitespace,
+//! so that a file can be edited by a program and written back out
+//! without mangling the parts that weren't touched.
+//!
+//! Writing a [`LosslessFile`] back out with [`Display`](std::fmt::Display)
+//! gives the exact bytes it was parsed from.
+
+use std::{borrow::Cow, fmt::Write, str::FromStr};
+
+use log::{info, trace};
+
+use super::{Deb822File, WHITESPACE, continuation_values};
+use crate::span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessFile {
+  pub stanzas: Vec<LosslessStanza>,
+  /// Blank lines and comments after the last stanza.
+  ///
+  /// Because a file ending in a newline splits into a last line of `""`,
+  /// this is where that final newline lives.
+  pub trailer: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessStanza {
+  /// Blank lines and comments between the previous stanza and this one.
+  pub leading: Vec<Trivia>,
+  pub fields: Vec<LosslessField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LosslessField {
+  pub name: String,
+  /// Everything after the `:` on the header line, whitespace and all.
+  pub value: String,
+  /// Continuation lines, and any comments mixed in with them.
+  pub lines: Vec<FieldLine>,
+}
+
+/// A line that is not part of any field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trivia {
+  /// A line with nothing but whitespace in it.
+  Blank(String),
+  /// A line whose first non-whitespace character is `#`.
+  Comment(String),
+}
+
+/// A line after a field header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldLine {
+  /// The whole line, including the leading whitespace.
+  Continuation(String),
+  Comment(String),
+}
+
+impl LosslessFile {
+  /// Turn this into a normal [`Deb822File`], forgetting the formatting.
+  pub fn to_deb822(&self) -> eyre::Result<Deb822File<'static>> {
+    Deb822File::from_str(&self.to_string())
+  }
+}
+
+impl LosslessStanza {
+  /// Find the first field named `name`, ignoring case.
+  pub fn field(&self, name: &str) -> Option<&LosslessField> {
+    self
+      .fields
+      .iter()
+      .find(|field| field.name.eq_ignore_ascii_case(name))
+  }
+
+  /// Find the first field named `name`, ignoring case.
+  pub fn field_mut(&mut self, name: &str) -> Option<&mut LosslessField> {
+    self
+      .fields
+      .iter_mut()
+      .find(|field| field.name.eq_ignore_ascii_case(name))
+  }
+
+  /// Add a new field to the end of the stanza.
+  pub fn push_field(
+    &mut self,
+    name: &str,
+    same_line_value: Option<&str>,
+    list_values: &[&str],
+  ) {
+    let mut field = LosslessField {
+      name: name.to_owned(),
+      value: String::new(),
+      lines: Vec::new(),
+    };
+    field.set_value(same_line_value, list_values);
+    self.fields.push(field);
+  }
+
+  /// Remove every field named `name`, ignoring case, returning whether
+  /// there were any.
+  pub fn remove_field(&mut self, name: &str) -> bool {
+    let before = self.fields.len();
+    self
+      .fields
+      .retain(|field| !field.name.eq_ignore_ascii_case(name));
+    before != self.fields.len()
+  }
+}
+
+impl LosslessField {
+  /// The value on the header line, trimmed the same way
+  /// [`Field::same_line_value`](super::Field::same_line_value) is.
+  pub fn same_line_value(&self) -> Option<&str> {
+    let value = self.value.trim_start_matches(WHITESPACE);
+    if value.is_empty() { None } else { Some(value) }
+  }
+
+  /// The continuation lines, without the comments, read the same way
+  /// [`Field::list_values`](super::Field::list_values) are.
+  pub fn list_values(&self) -> impl Iterator<Item = String> + use<> {
+    let values: Vec<String> =
+      continuation_values(self.lines.iter().filter_map(|line| match line {
+        FieldLine::Continuation(line) => Some(line.as_str()),
+        FieldLine::Comment(..) => None,
+      }))
+      .into_iter()
+      .map(Cow::into_owned)
+      .collect();
+    values.into_iter()
+  }
+
+  /// Replace the value of this field.
+  ///
+  /// New continuation lines are indented the same way as the first
+  /// old one, or with one space if there wasn't one.
+  /// Comments inside the old value are dropped.
+  pub fn set_value(
+    &mut self,
+    same_line_value: Option<&str>,
+    list_values: &[&str],
+  ) {
+    let indent = self
+      .lines
+      .iter()
+      .find_map(|line| match line {
+        FieldLine::Continuation(line) => {
+          let content = line.trim_start_matches(WHITESPACE);
+          Some(line[..line.len() - content.len()].to_owned())
+        }
+        FieldLine::Comment(..) => None,
+      })
+      .unwrap_or_else(|| " ".to_owned());
+
+    self.value = match same_line_value {
+      Some(value) => format!(" {}", value),
+      None => String::new(),
+    };
+    self.lines = list_values
+      .iter()
+      .map(|line| FieldLine::Continuation(format!("{}{}", indent, line)))
+      .collect();
+  }
+}
+
+impl FromStr for LosslessFile {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut stanzas: Vec<LosslessStanza> = Vec::new();
+    let mut pending_trivia = Vec::new();
+    // Whether the stanza at the end of `stanzas` can still get more fields.
+    let mut in_stanza = false;
+
+    for source_line in span::lines(s) {
+      let line = source_line.text;
+      if line.trim_start().starts_with('#') {
+        // Comments don't end a stanza, and comments in the middle of a field
+        // stay with the field.
+        match stanzas.last_mut().and_then(|st| st.fields.last_mut()) {
+          Some(field) if in_stanza => {
+            field.lines.push(FieldLine::Comment(line.to_owned()))
+          }
+          _ => pending_trivia.push(Trivia::Comment(line.to_owned())),
+        }
+      } else if in_stanza
+        && line.starts_with(WHITESPACE)
+        && !line.trim().is_empty()
+      {
+        if let Some(field) =
+          stanzas.last_mut().and_then(|st| st.fields.last_mut())
+        {
+          field.lines.push(FieldLine::Continuation(line.to_owned()));
+        }
+      } else if line.trim().is_empty() {
+        in_stanza = false;
+        pending_trivia.push(Trivia::Blank(line.to_owned()));
+      } else if line.starts_with(WHITESPACE) {
+        return Err(span::error(
+          s,
+          source_line.span(),
+          "field header must not start with whitespace",
+        ));
+      } else {
+        let (name, value) = line.split_once(':').ok_or_else(|| {
+          span::error(
+            s,
+            source_line.span(),
+            "could not find `:` in field header line",
+          )
+        })?;
+        trace!("lossless field header {:?} at {}", name, source_line.span());
+        let field = LosslessField {
+          name: name.to_owned(),
+          value: value.to_owned(),
+          lines: Vec::new(),
+        };
+        if !in_stanza {
+          stanzas.push(LosslessStanza {
+            leading: std::mem::take(&mut pending_trivia),
+            fields: Vec::new(),
+          });
+          in_stanza = true;
+        }
+        if let Some(stanza) = stanzas.last_mut() {
+          stanza.fields.push(field);
+        }
+      }
+    }
+
+    info!("parsed lossless Deb822 file with {} stanzas", stanzas.len());
+    Ok(LosslessFile {
+      stanzas,
+      trailer: pending_trivia,
+    })
+  }
+}
+
+impl std::fmt::Display for LosslessFile {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    // Lines are *separated* by newlines, so write one before every line
+    // except the first.
+    let mut first = true;
+    let mut line = |f: &mut std::fmt::Formatter<'_>| {
+      if !std::mem::take(&mut first) {
+        f.write_char('\n')?;
+      }
+      Ok(())
+    };
+
+    for stanza in &self.stanzas {
+      for trivia in &stanza.leading {
+        line(f)?;
+        f.write_str(trivia.as_str())?;
+      }
+      for field in &stanza.fields {
+        line(f)?;
+        write!(f, "{}:{}", &field.name, &field.value)?;
+        for field_line in &field.lines {
+          line(f)?;
+          f.write_str(field_line.as_str())?;
+        }
+      }
+    }
+    for trivia in &self.trailer {
+      line(f)?;
+      f.write_str(trivia.as_str())?;
+    }
+    Ok(())
+  }
+}
+
+impl Trivia {
+  pub fn as_str(&self) -> &str {
+    match self {
+      Trivia::Blank(s) | Trivia::Comment(s) => s,
+    }
+  }
+}
+
+impl FieldLine {
+  pub fn as_str(&self) -> &str {
+    match self {
+      FieldLine::Continuation(s) | FieldLine::Comment(s) => s,
+    }
+  }
+}
@@ -0,0 +1,213 @@
+//! OpenPGP clearsigned control files, like `.dsc` files from the archive.
+//!
+//! The format is in RFC 4880, section 7:
+//! https://www.rfc-editor.org/rfc/rfc4880#section-7
+//!
+//! ```text
+//! -----BEGIN PGP SIGNED MESSAGE-----
+//! Hash: SHA256
+//!
+//! Source: hello
+//! - -A line that started with a dash
+//! -----BEGIN PGP SIGNATURE-----
+//!
+//! iHUEARYIAB0WIQQW6w0XZWTjlYTHVgs6t3H3SisSzAUCatTTIQAKCRA6t3H3SisS
+//! ...
+//! -----END PGP SIGNATURE-----
+//! ```
+//!
+//! [`Deb822File`] strips the envelope off by itself, so this is only
+//! needed to get at the signature, or to check it.
+
+use std::{
+  io::Write,
+  path::Path,
+  process::{Command, Stdio},
+  str::FromStr,
+};
+
+use eyre::{Context, bail, eyre};
+use log::{debug, warn};
+
+use super::{Deb822File, DuplicateFields};
+use crate::span::{self, Line, SpannedError};
+
+const BEGIN_MESSAGE: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const BEGIN_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----";
+const END_SIGNATURE: &str = "-----END PGP SIGNATURE-----";
+
+/// A clearsigned file, split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedFile {
+  /// The hash algorithms from the `Hash:` armor headers, like `SHA256`.
+  pub hashes: Vec<String>,
+  /// The signed text, with the dash-escaping taken off.
+  /// Every line ends with a newline, including the last one.
+  pub body: String,
+  /// The whole armored signature block, from `-----BEGIN PGP SIGNATURE-----`
+  /// to `-----END PGP SIGNATURE-----`.
+  pub signature: String,
+  /// The file as it was, because that's what gets verified.
+  source: String,
+}
+
+/// The parts of a clearsigned file, still pointing into the source.
+pub(crate) struct Clearsigned<'s> {
+  pub(crate) hashes: Vec<&'s str>,
+  /// The body lines, without their line endings or dash-escaping.
+  pub(crate) body: Vec<Line<'s>>,
+  pub(crate) signature: &'s str,
+}
+
+impl SignedFile {
+  /// Whether `s` looks like a clearsigned file.
+  pub fn is_clearsigned(s: &str) -> bool {
+    s.trim_start().starts_with(BEGIN_MESSAGE)
+  }
+
+  /// Parse the signed body as a deb822 file.
+  pub fn deb822(&self) -> eyre::Result<Deb822File<'_>> {
+    Deb822File::parse(&self.body, DuplicateFields::Error)
+  }
+
+  /// Check the signature with `gpgv` against the keys in `keyring`.
+  ///
+  /// Returns the fingerprint of the key that made the signature.
+  pub fn verify<P: AsRef<Path>>(&self, keyring: P) -> eyre::Result<String> {
+    // gpgv looks for keyrings without a slash in them in ~/.gnupg
+    let keyring = std::path::absolute(keyring.as_ref())?;
+    let mut child = Command::new("gpgv")
+      .arg("--status-fd")
+      .arg("1")
+      .arg("--keyring")
+      .arg(&keyring)
+      .arg("-")
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .wrap_err("could not run gpgv")?;
+    child
+      .stdin
+      .take()
+      .ok_or_else(|| eyre!("gpgv has no stdin"))?
+      .write_all(self.source.as_bytes())
+      .wrap_err("could not write to gpgv")?;
+    let output = child.wait_with_output().wrap_err("could not run gpgv")?;
+
+    let status = String::from_utf8_lossy(&output.stdout);
+    debug!("gpgv status output:\n{}", status);
+    let fingerprint = status.lines().find_map(|line| {
+      let fields = line.strip_prefix("[GNUPG:] VALIDSIG ")?;
+      fields.split_whitespace().next()
+    });
+    match fingerprint {
+      Some(fingerprint) if output.status.success() => {
+        Ok(fingerprint.to_owned())
+      }
+      _ => bail!(
+        "signature did not verify against keyring {}: {}",
+        keyring.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
+      ),
+    }
+  }
+}
+
+impl FromStr for SignedFile {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parts =
+      split(s)?.ok_or_else(|| eyre!("file is not a clearsigned message"))?;
+    let mut body = String::new();
+    for line in &parts.body {
+      body.push_str(line.text);
+      body.push('\n');
+    }
+    Ok(SignedFile {
+      hashes: parts.hashes.iter().map(|hash| hash.to_string()).collect(),
+      body,
+      signature: parts.signature.to_owned(),
+      source: s.to_owned(),
+    })
+  }
+}
+
+/// Split a clearsigned file into its parts, or return `None` if it isn't
+/// one.
+pub(crate) fn split(s: &str) -> Result<Option<Clearsigned<'_>>, SpannedError> {
+  if !SignedFile::is_clearsigned(s) {
+    return Ok(None);
+  }
+  let error =
+    |line: &Line, message: &str| SpannedError::new(s, line.span(), message);
+  let mut lines = span::lines(s)
+    .map(|line| {
+      let len = line.text.strip_suffix('\r').unwrap_or(line.text).len();
+      line.slice(0, len)
+    })
+    .skip_while(|line| line.text.trim().is_empty());
+  // This is the BEGIN line
+  let Some(begin) = lines.next() else {
+    return Ok(None);
+  };
+
+  // Armor headers, up to an empty line
+  let mut hashes = Vec::new();
+  loop {
+    let Some(line) = lines.next() else {
+      return Err(error(&begin, "signed message has no body"));
+    };
+    if line.text.trim().is_empty() {
+      break;
+    }
+    match line.text.split_once(": ") {
+      Some(("Hash", value)) => hashes.extend(value.split(',').map(str::trim)),
+      Some((key, _)) => warn!(
+        "at {}: unexpected armor header {:?} in signed message",
+        line.span(),
+        key
+      ),
+      None => return Err(error(&line, "malformed armor header")),
+    }
+  }
+
+  // The body, up to the signature
+  let mut body = Vec::new();
+  let signature_start = loop {
+    let Some(line) = lines.next() else {
+      return Err(error(&begin, "signed message has no signature"));
+    };
+    if line.text == BEGIN_SIGNATURE {
+      break line;
+    }
+    if line.text.starts_with("- ") {
+      body.push(line.slice(2, line.text.len()));
+    } else if line.text.starts_with('-') {
+      return Err(error(&line, "line starting with `-` is not dash-escaped"));
+    } else {
+      body.push(line);
+    }
+  };
+
+  // The signature block
+  let signature_end = loop {
+    let Some(line) = lines.next() else {
+      return Err(error(&signature_start, "signature block is not closed"));
+    };
+    if line.text == END_SIGNATURE {
+      break line;
+    }
+  };
+  if let Some(line) = lines.find(|line| !line.text.trim().is_empty()) {
+    warn!("at {}: ignoring text after the signature", line.span());
+  }
+
+  let signature = signature_start.span().to(signature_end.span());
+  Ok(Some(Clearsigned {
+    hashes,
+    body,
+    signature: signature.text(s),
+  }))
+}
@@ -0,0 +1,156 @@
+//! Reading a file a stanza at a time, for files too big to read in whole,
+//! like the `Packages` and `Sources` indices of an archive.
+//!
+//! ```no_run
+//! use std::{fs::File, io::BufReader};
+//!
+//! use deb_strip_copyright::deb822::{DuplicateFields, stream::StanzaReader};
+//!
+//! let file = BufReader::new(File::open("Sources")?);
+//! for stanza in StanzaReader::new(file, DuplicateFields::Error) {
+//!   let stanza = stanza?;
+//!   // ...
+//! }
+//! # Ok::<(), eyre::Report>(())
+//! ```
+//!
+//! Archive indices usually come compressed, as `Sources.xz` or
+//! `Packages.gz`. [`StanzaReader::open`] decompresses them on the fly.
+
+use std::{io::BufRead, path::Path};
+
+use eyre::Context;
+use log::trace;
+
+use super::{DuplicateFields, Parser, Stanza};
+use crate::{compression, span};
+
+/// Reads stanzas from `R` one at a time, keeping only the current one in
+/// memory.
+///
+/// A stanza with a syntax error in it comes back as an error, and the
+/// next one is read as normal after it. Spans and errors have positions
+/// in the whole input. An I/O error, like text that isn't UTF-8, ends the
+/// reading, since there's no telling where the next stanza starts.
+///
+/// Clearsigned input isn't supported. Use
+/// [`Deb822File::parse`](super::Deb822File::parse) for that.
+pub struct StanzaReader<R> {
+  reader: R,
+  duplicates: DuplicateFields,
+  /// The text of the current stanza, including comments.
+  buf: String,
+  /// Where the next line starts, in bytes from the start of the input.
+  offset: usize,
+  /// The number of the next line, counting from 1.
+  line: usize,
+  /// Set after an I/O error.
+  done: bool,
+}
+
+impl<R: BufRead> StanzaReader<R> {
+  /// Read from `reader`, handling duplicate field names the way
+  /// `duplicates` says.
+  pub fn new(reader: R, duplicates: DuplicateFields) -> Self {
+    Self {
+      reader,
+      duplicates,
+      buf: String::new(),
+      offset: 0,
+      line: 1,
+      done: false,
+    }
+  }
+
+  /// Read the next stanza, or return `None` at the end of the input.
+  ///
+  /// The stanza borrows from the reader, so nothing in it is copied. The
+  /// [`Iterator`] impl copies each one, so they can be kept.
+  pub fn read_stanza(&mut self) -> Option<eyre::Result<Stanza<'_>>> {
+    let (offset, line) = match self.fill() {
+      Ok(Some(start)) => start,
+      Ok(None) => return None,
+      Err(err) => {
+        self.done = true;
+        return Some(Err(err));
+      }
+    };
+
+    let parser = Parser {
+      source: &self.buf,
+      duplicates: self.duplicates,
+    };
+    let mut lines = span::lines(&self.buf)
+      .filter(|line| !line.text.trim_start().starts_with('#'))
+      .peekable();
+    let result = match parser.eat_stanza(&mut lines) {
+      Ok(mut stanza) => {
+        stanza.shift_spans(offset, line);
+        Ok(stanza)
+      }
+      Err(err) => Err(err.shifted(offset, line).into()),
+    };
+    Some(result)
+  }
+
+  /// Read the lines of the next stanza into `buf`, and return where it
+  /// starts: its offset and line number.
+  fn fill(&mut self) -> eyre::Result<Option<(usize, usize)>> {
+    self.buf.clear();
+    if self.done {
+      return Ok(None);
+    }
+
+    let mut start = None;
+    loop {
+      let len = self.buf.len();
+      let read = self
+        .reader
+        .read_line(&mut self.buf)
+        .wrap_err_with(|| format!("could not read line {}", self.line))?;
+      if read == 0 {
+        break;
+      }
+      let text = &self.buf[len..];
+      let blank = text.trim().is_empty();
+      let comment = text.trim_start().starts_with('#');
+      let (offset, line) = (self.offset, self.line);
+      self.offset += read;
+      self.line += 1;
+
+      if start.is_none() {
+        if blank || comment {
+          // Between stanzas
+          self.buf.truncate(len);
+          continue;
+        }
+        trace!("stanza starts at line {}", line);
+        start = Some((offset, line));
+      } else if blank {
+        self.buf.truncate(len);
+        break;
+      }
+    }
+    Ok(start)
+  }
+}
+
+impl StanzaReader<Box<dyn BufRead>> {
+  /// Read the file at `path`, decompressing it first if it's xz or gzip.
+  pub fn open<P: AsRef<Path>>(
+    path: P,
+    duplicates: DuplicateFields,
+  ) -> eyre::Result<Self> {
+    Ok(Self::new(compression::open(path)?, duplicates))
+  }
+}
+
+impl<R: BufRead> Iterator for StanzaReader<R> {
+  type Item = eyre::Result<Stanza<'static>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self
+      .read_stanza()
+      .map(|result| result.map(Stanza::into_owned))
+  }
+}
@@ -0,0 +1,169 @@
+//! Composable rules for deciding which entries get excluded from a tarball.
+//!
+//! [`CopyrightFile`](crate::deb822::copyright::CopyrightFile) is the main
+//! filter, but anything implementing [`PathFilter`] can be handed to
+//! [`Stripper`](crate::strip::Stripper).
+
+use std::str::FromStr;
+
+use eyre::{Context, eyre};
+
+use crate::{
+  glob::{Glob, set::GlobSet},
+  strip::EntryInfo,
+};
+
+/// Something that can decide whether a tarball entry should be excluded.
+///
+/// Most filters only look at [`EntryInfo::path`], which is relative to the
+/// root of the source tree, ie without the `foo-1.0/` directory at the
+/// front of the tarball. The rest of the entry is there for rules about
+/// sizes, modes and so on.
+pub trait PathFilter {
+  /// Return `true` if `entry` should be excluded.
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool;
+
+  /// Exclude a path only if both `self` and `other` exclude it.
+  fn and<F: PathFilter>(self, other: F) -> And<Self, F>
+  where
+    Self: Sized,
+  {
+    And(self, other)
+  }
+
+  /// Exclude a path if either `self` or `other` excludes it.
+  fn or<F: PathFilter>(self, other: F) -> Or<Self, F>
+  where
+    Self: Sized,
+  {
+    Or(self, other)
+  }
+
+  /// Exclude exactly the paths that `self` keeps.
+  fn not(self) -> Not<Self>
+  where
+    Self: Sized,
+  {
+    Not(self)
+  }
+}
+
+impl<T: PathFilter + ?Sized> PathFilter for &T {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    (**self).is_excluded(entry)
+  }
+}
+
+impl<T: PathFilter + ?Sized> PathFilter for Box<T> {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    (**self).is_excluded(entry)
+  }
+}
+
+/// See [`PathFilter::and`].
+#[derive(Debug, Clone)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: PathFilter, B: PathFilter> PathFilter for And<A, B> {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    self.0.is_excluded(entry) && self.1.is_excluded(entry)
+  }
+}
+
+/// See [`PathFilter::or`].
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: PathFilter, B: PathFilter> PathFilter for Or<A, B> {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    self.0.is_excluded(entry) || self.1.is_excluded(entry)
+  }
+}
+
+/// See [`PathFilter::not`].
+#[derive(Debug, Clone)]
+pub struct Not<A>(pub A);
+
+impl<A: PathFilter> PathFilter for Not<A> {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    !self.0.is_excluded(entry)
+  }
+}
+
+/// Filter built out of a closure. See [`from_fn`].
+#[derive(Debug, Clone)]
+pub struct FnFilter<F>(F);
+
+/// Make a filter that excludes an entry when `f` returns `true` for it.
+///
+/// This is how to plug in rules that globs can't express, like size limits.
+pub fn from_fn<F: Fn(&EntryInfo<'_>) -> bool>(f: F) -> FnFilter<F> {
+  FnFilter(f)
+}
+
+impl<F: Fn(&EntryInfo<'_>) -> bool> PathFilter for FnFilter<F> {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    (self.0)(entry)
+  }
+}
+
+/// Plain list of globs; a path is excluded if any of them match.
+///
+/// This is what `--exclude` and `--exclude-from` turn into.
+#[derive(Debug, Clone, Default)]
+pub struct GlobList {
+  globs: GlobSet,
+}
+
+impl GlobList {
+  pub fn new(globs: Vec<Glob>) -> Self {
+    Self {
+      globs: GlobSet::new(globs),
+    }
+  }
+
+  /// Add a glob. This recompiles the whole list, so prefer
+  /// [`GlobList::new`] for lots of globs at once.
+  pub fn push(&mut self, glob: Glob) {
+    self.extend(GlobList::new(vec![glob]));
+  }
+
+  pub fn extend(&mut self, other: GlobList) {
+    let mut globs = std::mem::take(&mut self.globs).into_globs();
+    globs.extend(other.globs.into_globs());
+    self.globs = GlobSet::new(globs);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.globs.is_empty()
+  }
+
+  pub fn into_globs(self) -> Vec<Glob> {
+    self.globs.into_globs()
+  }
+}
+
+impl PathFilter for GlobList {
+  fn is_excluded(&self, entry: &EntryInfo<'_>) -> bool {
+    self.globs.is_match(entry.path)
+  }
+}
+
+/// Parse whitespace-separated globs, same as a `Files-Excluded` field.
+/// Lines starting with `#` are comments.
+impl FromStr for GlobList {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let globs: Result<Vec<_>, _> = s
+      .lines()
+      .filter(|line| !line.trim_start().starts_with('#'))
+      .flat_map(|line| line.split_ascii_whitespace())
+      .map(|glob_str| {
+        Glob::from_str(glob_str)
+          .wrap_err_with(|| eyre!("while parsing glob string {:?}", glob_str))
+      })
+      .collect();
+    Ok(Self::new(globs?))
+  }
+}
@@ -1,7 +1,9 @@
 //! Simple Glob implementation that only allows `*`, `?`, and escapes.
 //! This is in accordance with Debian copyright syntax.
 
-use std::{fmt::Write, str::FromStr};
+pub mod set;
+
+use std::{ffi::OsStr, fmt::Write, str::FromStr};
 
 use eyre::eyre;
 
@@ -10,12 +12,12 @@ use eyre::eyre;
 /// The documentation does not say whether the `*` is greedy or ungreedy.
 /// This implementation assumes ungreedy. That is, it will match as few
 /// characters as possible.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Glob {
   segments: Vec<GlobSegment>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 enum GlobSegment {
   Literal(String),
   Star,
@@ -23,8 +25,20 @@ enum GlobSegment {
 }
 
 impl Glob {
-  /// Check if this glob matches the given string.
-  pub fn matches<S: AsRef<str>>(&self, s: S) -> bool {
+  /// Check if this glob matches the given string or path.
+  ///
+  /// Paths don't have to be UTF-8. See [`Glob::matches_bytes`].
+  pub fn matches<S: AsRef<OsStr>>(&self, s: S) -> bool {
+    self.matches_bytes(s.as_ref().as_encoded_bytes())
+  }
+
+  /// Check if this glob matches the given bytes.
+  ///
+  /// Literal parts of the glob are compared byte for byte, so a name that
+  /// isn't valid UTF-8, like a Latin-1 one from an old tarball, only
+  /// matches where it has the same bytes as the glob. A `?` matches one
+  /// UTF-8 character, or one byte that isn't part of a valid character.
+  pub fn matches_bytes(&self, s: &[u8]) -> bool {
     if self.is_empty() {
       // It is contentious whether this should match everything or nothing.
       // IMHO, an empty glob feels like a user error, so it should fail-safe
@@ -33,26 +47,23 @@ impl Glob {
       return false;
     }
 
-    let mut s_slice = s.as_ref();
+    let mut s_slice = s;
 
     let mut peeker = self.segments.iter().peekable();
     // Peekable's mutability doesn't generally agree with for loops
     while let Some(seg) = peeker.next() {
       match seg {
         GlobSegment::Literal(lit) => {
-          if let Some(rest) = s_slice.strip_prefix(lit) {
+          if let Some(rest) = s_slice.strip_prefix(lit.as_bytes()) {
             s_slice = rest;
           } else {
             return false;
           }
         }
         GlobSegment::Question => {
-          // `take` is the first character.
-          // we want the *second*. But because of zero-indexing,
-          // nth(1) does that.
-          let next_ch = s_slice.char_indices().nth(1);
-          if let Some((idx, _)) = next_ch {
-            s_slice = &s_slice[idx..];
+          let len = first_char_len(s_slice);
+          if len < s_slice.len() {
+            s_slice = &s_slice[len..];
           } else {
             // Else we ate the entire string.
             return true;
@@ -67,7 +78,7 @@ impl Glob {
             // this should be forbidden by the FromStr impl
             panic!("cannot have a `*` followed by a wildcard!");
           };
-          if let Some(start_idx) = s_slice.find(next_lit) {
+          if let Some(start_idx) = find(s_slice, next_lit.as_bytes()) {
             // Slice away everything up to that point.
             s_slice = &s_slice[start_idx..];
             // The next iteration will redo the work of checking for this
@@ -87,11 +98,11 @@ impl Glob {
     // IE `vendor/libfoobar-1.0.0` matches "vendor/libfoobar-1.0.0/Cargo.toml"
     // In the above example, s_slice would be "/libfoobar-1.0.0/Cargo.toml" when
     // control flow reaches here.
-    let Some(next_char) = s_slice.chars().next() else {
+    match s_slice.first() {
       // if it's empty, we're set
-      return true;
-    };
-    next_char == '/'
+      None => true,
+      Some(next_byte) => *next_byte == b'/',
+    }
   }
 
   /// Return if this glob is empty.
@@ -101,6 +112,24 @@ impl Glob {
   }
 }
 
+/// How many bytes the first character of `s` takes up. Bytes that aren't
+/// part of a valid UTF-8 character count as one character each.
+fn first_char_len(s: &[u8]) -> usize {
+  let start = &s[..s.len().min(4)];
+  let valid = match std::str::from_utf8(start) {
+    Ok(valid) => valid,
+    Err(err) => std::str::from_utf8(&start[..err.valid_up_to()]).unwrap_or(""),
+  };
+  valid.chars().next().map_or(1, char::len_utf8)
+}
+
+/// Find the first place `needle` appears in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack
+    .windows(needle.len())
+    .position(|window| window == needle)
+}
+
 impl FromStr for Glob {
   type Err = eyre::Error;
 
@@ -161,6 +190,28 @@ impl FromStr for Glob {
   }
 }
 
+/// Write the glob back out in the syntax [`Glob::from_str`] reads,
+/// escaping wildcard characters in literals.
+impl std::fmt::Display for Glob {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for seg in &self.segments {
+      match seg {
+        GlobSegment::Literal(lit) => {
+          for c in lit.chars() {
+            if c == '\\' || c == '*' || c == '?' {
+              f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+          }
+        }
+        GlobSegment::Star => f.write_char('*')?,
+        GlobSegment::Question => f.write_char('?')?,
+      }
+    }
+    Ok(())
+  }
+}
+
 impl std::fmt::Debug for GlobSegment {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
@@ -170,3 +221,19 @@ impl std::fmt::Debug for GlobSegment {
     }
   }
 }
+
+/// Globs are written as their source text.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Glob {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(self)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Glob {
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    let s = String::deserialize(d)?;
+    Glob::from_str(&s).map_err(serde::de::Error::custom)
+  }
+}
@@ -0,0 +1,287 @@
+//! Matching a path against many globs at once.
+//!
+//! Checking globs one at a time is fine for a handful of them, but
+//! rustc's `Files-Excluded` lists hundreds of vendored crates, and its
+//! tarball has hundreds of thousands of entries. [`GlobSet`] files each
+//! glob under a literal part it has to contain:
+//!
+//! - Globs that start with a literal, like `vendor/foo-*`, go in a trie of
+//!   those prefixes, which the path is walked down once.
+//! - Globs that start with `*`, like `*.min.js`, have a literal later on
+//!   that must be somewhere in the path. Those go in an Aho–Corasick
+//!   automaton, which finds all of them in one pass over the path.
+//! - The few left over, like `?` on its own, are tried on every path.
+//!
+//! The globs that turn up are only candidates, and each one is then
+//! checked with [`Glob::matches_bytes`], so a set matches exactly what its
+//! globs do one by one. There are rarely more than a couple of candidates,
+//! so a lookup takes time about linear in the length of the path, however
+//! many globs there are.
+
+use std::{collections::VecDeque, ffi::OsStr};
+
+use super::{Glob, GlobSegment};
+
+/// A list of globs compiled to be matched all at once.
+///
+/// Two sets are equal if they have the same globs in the same order.
+#[derive(Clone, Default)]
+pub struct GlobSet {
+  globs: Vec<Glob>,
+  /// Literal prefixes of globs that start with one.
+  prefixes: Trie,
+  /// Literals that must appear somewhere in the path, for globs that
+  /// start with a wildcard.
+  literals: Trie,
+  /// Globs that have nothing to look for, so are always candidates.
+  unfiltered: Vec<usize>,
+}
+
+impl GlobSet {
+  pub fn new(globs: Vec<Glob>) -> Self {
+    let mut prefixes = Trie::default();
+    let mut literals = Trie::default();
+    let mut unfiltered = Vec::new();
+    for (idx, glob) in globs.iter().enumerate() {
+      if let Some(GlobSegment::Literal(prefix)) = glob.segments.first() {
+        prefixes.insert(prefix.as_bytes(), idx);
+      } else if let Some(literal) = required_literal(glob) {
+        literals.insert(literal.as_bytes(), idx);
+      } else {
+        unfiltered.push(idx);
+      }
+    }
+    literals.link();
+
+    Self {
+      globs,
+      prefixes,
+      literals,
+      unfiltered,
+    }
+  }
+
+  /// The globs in the set, in the order they were given.
+  pub fn globs(&self) -> &[Glob] {
+    &self.globs
+  }
+
+  pub fn into_globs(self) -> Vec<Glob> {
+    self.globs
+  }
+
+  pub fn len(&self) -> usize {
+    self.globs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.globs.is_empty()
+  }
+
+  /// Return the indices of every glob that matches, in increasing order.
+  pub fn matches<S: AsRef<OsStr>>(&self, s: S) -> Vec<usize> {
+    self.matches_bytes(s.as_ref().as_encoded_bytes())
+  }
+
+  /// Return the indices of every glob that matches these bytes, in
+  /// increasing order.
+  pub fn matches_bytes(&self, s: &[u8]) -> Vec<usize> {
+    let mut candidates = self.unfiltered.clone();
+    let mut push = |idx| {
+      candidates.push(idx);
+      false
+    };
+    self.prefixes.find_prefixes(s, &mut push);
+    self.literals.find_anywhere(s, &mut push);
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates.retain(|&idx| self.globs[idx].matches_bytes(s));
+    candidates
+  }
+
+  /// Check if any glob in the set matches.
+  pub fn is_match<S: AsRef<OsStr>>(&self, s: S) -> bool {
+    self.is_match_bytes(s.as_ref().as_encoded_bytes())
+  }
+
+  /// Check if any glob in the set matches these bytes.
+  ///
+  /// This stops at the first glob that matches, so it's cheaper than
+  /// [`GlobSet::matches_bytes`] when the indices don't matter.
+  pub fn is_match_bytes(&self, s: &[u8]) -> bool {
+    let mut check = |idx: usize| self.globs[idx].matches_bytes(s);
+    self.unfiltered.iter().any(|&idx| check(idx))
+      || self.prefixes.find_prefixes(s, &mut check)
+      || self.literals.find_anywhere(s, &mut check)
+  }
+}
+
+/// The longest literal, other than a literal prefix, that every path
+/// `glob` matches has to contain.
+fn required_literal(glob: &Glob) -> Option<&str> {
+  glob
+    .segments
+    .iter()
+    // A `?` on the last character of the path ends the match early, so
+    // nothing after one is required
+    .take_while(|seg| !matches!(seg, GlobSegment::Question))
+    .filter_map(|seg| match seg {
+      GlobSegment::Literal(lit) => Some(lit.as_str()),
+      _ => None,
+    })
+    .max_by_key(|lit| lit.len())
+}
+
+impl FromIterator<Glob> for GlobSet {
+  fn from_iter<I: IntoIterator<Item = Glob>>(iter: I) -> Self {
+    Self::new(iter.into_iter().collect())
+  }
+}
+
+/// Debug-prints as just the list of globs.
+impl std::fmt::Debug for GlobSet {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_list().entries(&self.globs).finish()
+  }
+}
+
+impl PartialEq for GlobSet {
+  fn eq(&self, other: &Self) -> bool {
+    self.globs == other.globs
+  }
+}
+
+impl Eq for GlobSet {}
+
+/// Sets are written as their list of globs.
+#[cfg(feature = "serde")]
+impl serde::Serialize for GlobSet {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    self.globs.serialize(s)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for GlobSet {
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    Vec::<Glob>::deserialize(d).map(GlobSet::new)
+  }
+}
+
+/// Byte trie, which is also an Aho–Corasick automaton once
+/// [`Trie::link`] has been called. Node `0` is the root.
+#[derive(Clone)]
+struct Trie {
+  nodes: Vec<Node>,
+}
+
+#[derive(Clone, Default)]
+struct Node {
+  /// Child nodes, sorted by byte.
+  next: Vec<(u8, usize)>,
+  /// The node for the longest proper suffix of this node's string that is
+  /// also in the trie.
+  fail: usize,
+  /// Globs whose literal ends here. After linking, this includes the ones
+  /// whose literal is a suffix of this node's string.
+  out: Vec<usize>,
+}
+
+impl Default for Trie {
+  fn default() -> Self {
+    Self {
+      nodes: vec![Node::default()],
+    }
+  }
+}
+
+impl Trie {
+  fn child(&self, node: usize, byte: u8) -> Option<usize> {
+    let next = &self.nodes[node].next;
+    next
+      .binary_search_by_key(&byte, |&(b, _)| b)
+      .ok()
+      .map(|idx| next[idx].1)
+  }
+
+  fn insert(&mut self, literal: &[u8], glob: usize) {
+    let mut node = 0;
+    for &byte in literal {
+      let next = &self.nodes[node].next;
+      node = match next.binary_search_by_key(&byte, |&(b, _)| b) {
+        Ok(idx) => next[idx].1,
+        Err(idx) => {
+          let child = self.nodes.len();
+          self.nodes.push(Node::default());
+          self.nodes[node].next.insert(idx, (byte, child));
+          child
+        }
+      };
+    }
+    self.nodes[node].out.push(glob);
+  }
+
+  /// Fill in the failure links. This goes breadth first, so every node's
+  /// link target is finished before the node itself.
+  fn link(&mut self) {
+    let mut queue: VecDeque<usize> =
+      self.nodes[0].next.iter().map(|&(_, child)| child).collect();
+    while let Some(node) = queue.pop_front() {
+      for idx in 0..self.nodes[node].next.len() {
+        let (byte, child) = self.nodes[node].next[idx];
+        let mut fail = self.nodes[node].fail;
+        let target = loop {
+          if let Some(target) = self.child(fail, byte) {
+            break target;
+          }
+          if fail == 0 {
+            break 0;
+          }
+          fail = self.nodes[fail].fail;
+        };
+        self.nodes[child].fail = target;
+        let inherited = self.nodes[target].out.clone();
+        self.nodes[child].out.extend(inherited);
+        queue.push_back(child);
+      }
+    }
+  }
+
+  /// Call `f` on each glob whose literal is a prefix of `s`, until it
+  /// returns `true`. Return whether it did.
+  fn find_prefixes(&self, s: &[u8], f: &mut impl FnMut(usize) -> bool) -> bool {
+    let mut node = 0;
+    for &byte in s {
+      let Some(child) = self.child(node, byte) else {
+        return false;
+      };
+      node = child;
+      if self.nodes[node].out.iter().any(|&idx| f(idx)) {
+        return true;
+      }
+    }
+    false
+  }
+
+  /// Call `f` on each glob whose literal appears anywhere in `s`, until it
+  /// returns `true`. Return whether it did.
+  /// Only works after [`Trie::link`].
+  fn find_anywhere(&self, s: &[u8], f: &mut impl FnMut(usize) -> bool) -> bool {
+    let mut node = 0;
+    for &byte in s {
+      node = loop {
+        if let Some(child) = self.child(node, byte) {
+          break child;
+        }
+        if node == 0 {
+          break 0;
+        }
+        node = self.nodes[node].fail;
+      };
+      if self.nodes[node].out.iter().any(|&idx| f(idx)) {
+        return true;
+      }
+    }
+    false
+  }
+}
@@ -1,3 +1,16 @@
+pub mod changelog;
+pub mod compression;
+#[cfg(feature = "serde")]
+pub mod convert;
+pub mod coverage;
 pub mod deb822;
+pub mod filter;
 pub mod glob;
+pub mod license;
+pub mod lint;
+pub mod relation;
+pub mod sbom;
+pub mod span;
 pub mod strip;
+pub mod survey;
+pub mod version;
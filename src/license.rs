@@ -0,0 +1,652 @@
+//! Parse the license expressions in `License` fields, and translate
+//! Debian's license short names to SPDX identifiers.
+//!
+//! The syntax is in the "License specification" section of
+//! https://www.debian.org/doc/packaging-manuals/copyright-format/1.0
+//!
+//! Precedence, tightest first: `with`, `and`, `or`, and then a comma
+//! followed by `and` or `or`. So `GPL-2+ or Artistic-2.0, and BSD-3-clause`
+//! is `(GPL-2+ or Artistic-2.0) and BSD-3-clause`.
+//! The keywords are case-insensitive, to also accept SPDX-style
+//! `MIT OR Apache-2.0`, and brackets group like in SPDX too. Commas can't
+//! express everything, so expressions are written back out with brackets
+//! where they would otherwise parse differently.
+
+use std::str::FromStr;
+
+use eyre::{bail, eyre};
+
+/// Parsed license expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+  License(LicenseName),
+  /// All of these licenses apply.
+  And(Vec<LicenseExpr>),
+  /// Any of these licenses may be chosen.
+  Or(Vec<LicenseExpr>),
+}
+
+/// One license in an expression, like `GPL-2+ with OpenSSL exception`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseName {
+  /// The short name, as written, like `GPL-2+`.
+  pub name: String,
+  /// The exception, without the trailing word `exception` if it was
+  /// written DEP-5 style, so `OpenSSL` or `LLVM-exception`.
+  pub exception: Option<String>,
+}
+
+impl LicenseExpr {
+  /// Every license name in the expression, left to right.
+  pub fn names(&self) -> Vec<&LicenseName> {
+    let mut out = Vec::new();
+    self.collect_names(&mut out);
+    out
+  }
+
+  fn collect_names<'a>(&'a self, out: &mut Vec<&'a LicenseName>) {
+    match self {
+      LicenseExpr::License(name) => out.push(name),
+      LicenseExpr::And(exprs) | LicenseExpr::Or(exprs) => {
+        for expr in exprs {
+          expr.collect_names(out);
+        }
+      }
+    }
+  }
+
+  /// The names (including exceptions) that have no SPDX equivalent.
+  pub fn unknown_names(&self) -> Vec<String> {
+    self
+      .names()
+      .into_iter()
+      .filter(|name| name.to_spdx().is_none())
+      .map(|name| name.to_string())
+      .collect()
+  }
+
+  /// Translate into an SPDX license expression.
+  /// Fails if any name has no SPDX equivalent.
+  pub fn to_spdx(&self) -> eyre::Result<String> {
+    let unknown = self.unknown_names();
+    if !unknown.is_empty() {
+      bail!("no SPDX identifier for {}", unknown.join(", "));
+    }
+    Ok(self.render_spdx(Precedence::Top))
+  }
+
+  /// Translate into an SPDX license expression, turning names without an
+  /// SPDX equivalent into `LicenseRef-` identifiers.
+  pub fn to_spdx_lossy(&self) -> String {
+    self.render_spdx(Precedence::Top)
+  }
+
+  fn render_spdx(&self, parent: Precedence) -> String {
+    let (children, op, prec) = match self {
+      LicenseExpr::License(name) => {
+        let spdx = name.to_spdx().unwrap_or_else(|| name.license_ref());
+        // Some Debian names turn into compound expressions
+        let compound = spdx.contains(" OR ") || spdx.contains(" AND ");
+        return if compound && parent != Precedence::Top {
+          format!("({})", spdx)
+        } else {
+          spdx
+        };
+      }
+      LicenseExpr::And(children) => (children, " AND ", Precedence::And),
+      LicenseExpr::Or(children) => (children, " OR ", Precedence::Or),
+    };
+    let inner = children
+      .iter()
+      .map(|child| child.render_spdx(prec))
+      .collect::<Vec<_>>()
+      .join(op);
+    // AND binds tighter than OR, so only an OR inside an AND needs parens
+    if parent == Precedence::And && prec == Precedence::Or {
+      format!("({})", inner)
+    } else {
+      inner
+    }
+  }
+
+  /// Does writing this out need commas, because it has an `or` inside
+  /// an `and`?
+  ///
+  /// Commas chain to the left, so only the first child's commas carry
+  /// over. The other children are bracketed if they need commas, see
+  /// [`LicenseExpr::needs_brackets`].
+  fn needs_comma(&self) -> bool {
+    match self {
+      LicenseExpr::License(..) => false,
+      LicenseExpr::Or(children) => {
+        children.first().is_some_and(Self::needs_comma)
+      }
+      LicenseExpr::And(children) => {
+        children.first().is_some_and(Self::needs_comma)
+          || children
+            .iter()
+            .any(|child| matches!(child, LicenseExpr::Or(..)))
+      }
+    }
+  }
+
+  /// Does the child at `idx` have to be bracketed?
+  ///
+  /// Only the first child can be a whole comma expression. After a comma
+  /// the parser reads an `or` expression, which binds tighter, so a child
+  /// there that needs commas itself goes in brackets.
+  fn needs_brackets(idx: usize, child: &LicenseExpr) -> bool {
+    idx != 0 && child.needs_comma()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precedence {
+  Top,
+  And,
+  Or,
+}
+
+impl LicenseName {
+  /// The SPDX expression for this license, if there is one.
+  ///
+  /// This is usually just an identifier, but Debian names like `Perl`
+  /// stand for several licenses.
+  pub fn to_spdx(&self) -> Option<String> {
+    let id = debian_to_spdx(&self.name)?;
+    match &self.exception {
+      None => Some(id),
+      // Can't put an exception on a compound expression
+      Some(_) if id.contains(' ') => None,
+      Some(exception) => {
+        let exception = exception_to_spdx(exception)?;
+        Some(format!("{} WITH {}", id, exception))
+      }
+    }
+  }
+
+  /// A made-up SPDX identifier, for licenses without a real one.
+  pub(crate) fn license_ref(&self) -> String {
+    let sanitized: String = self
+      .to_string()
+      .chars()
+      .map(|c| {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+          c
+        } else {
+          '-'
+        }
+      })
+      .collect();
+    format!("LicenseRef-{}", sanitized)
+  }
+}
+
+/// Translate a single Debian license short name into SPDX.
+///
+/// Names that already are SPDX identifiers are accepted too, in any case.
+pub fn debian_to_spdx(name: &str) -> Option<String> {
+  if let Some(id) = gnu_to_spdx(name) {
+    return Some(id);
+  }
+
+  // Non-GNU licenses use SPDX's `+` operator for "or later"
+  let (base, or_later) = match name.strip_suffix('+') {
+    Some(base) => (base, true),
+    None => (name, false),
+  };
+  let id = DEBIAN_NAMES
+    .iter()
+    .find(|(debian, _)| debian.eq_ignore_ascii_case(base))
+    .map(|(_, spdx)| *spdx)
+    .or_else(|| {
+      SPDX_IDS
+        .iter()
+        .find(|spdx| spdx.eq_ignore_ascii_case(base))
+        .copied()
+    })
+    .map(str::to_owned)
+    .or_else(|| creative_commons(base))?;
+
+  if or_later {
+    if id.contains(' ') {
+      return None;
+    }
+    Some(format!("{}+", id))
+  } else {
+    Some(id)
+  }
+}
+
+/// GPL and friends have different SPDX identifiers for "only" and
+/// "or later", instead of using `+`.
+fn gnu_to_spdx(name: &str) -> Option<String> {
+  let upper = name.to_ascii_uppercase();
+  let (family, rest) = ["GFDL-NIV", "AGPL", "LGPL", "GFDL", "GPL"]
+    .iter()
+    .find_map(|family| Some((*family, upper.strip_prefix(family)?)))?;
+  let rest = rest.strip_prefix('-')?;
+
+  let (version, or_later) = if let Some(v) = rest.strip_suffix('+') {
+    (v, true)
+  } else if let Some(v) = rest.strip_suffix("-OR-LATER") {
+    (v, true)
+  } else if let Some(v) = rest.strip_suffix("-ONLY") {
+    (v, false)
+  } else {
+    (rest, false)
+  };
+  let version = match (family, version) {
+    ("GFDL" | "GFDL-NIV", "1.1" | "1.2" | "1.3") => version.to_owned(),
+    ("GFDL" | "GFDL-NIV", _) => return None,
+    ("LGPL", "2.1") => version.to_owned(),
+    (_, "1" | "2" | "3") => format!("{}.0", version),
+    (_, "1.0" | "2.0" | "3.0") => version.to_owned(),
+    _ => return None,
+  };
+  let valid = match family {
+    "GPL" => ["1.0", "2.0", "3.0"].contains(&&*version),
+    "LGPL" => ["2.0", "2.1", "3.0"].contains(&&*version),
+    "AGPL" => ["1.0", "3.0"].contains(&&*version),
+    _ => true,
+  };
+  if !valid {
+    return None;
+  }
+
+  let suffix = if or_later { "or-later" } else { "only" };
+  Some(match family {
+    "GFDL-NIV" => format!("GFDL-{}-no-invariants-{}", version, suffix),
+    _ => format!("{}-{}-{}", family, version, suffix),
+  })
+}
+
+/// `CC-BY-SA-4.0` and friends are spelled the same in Debian and SPDX.
+fn creative_commons(name: &str) -> Option<String> {
+  let upper = name.to_ascii_uppercase();
+  let rest = upper.strip_prefix("CC-BY")?;
+  let (variant, version) = rest.rsplit_once('-')?;
+  let variant_ok =
+    ["", "-SA", "-ND", "-NC", "-NC-SA", "-NC-ND"].contains(&variant);
+  let version_ok = ["1.0", "2.0", "2.5", "3.0", "4.0"].contains(&version);
+  (variant_ok && version_ok).then(|| format!("CC-BY{}-{}", variant, version))
+}
+
+fn exception_to_spdx(exception: &str) -> Option<String> {
+  DEBIAN_EXCEPTIONS
+    .iter()
+    .find(|(debian, _)| debian.eq_ignore_ascii_case(exception))
+    .map(|(_, spdx)| *spdx)
+    .or_else(|| {
+      SPDX_EXCEPTIONS
+        .iter()
+        .find(|spdx| spdx.eq_ignore_ascii_case(exception))
+        .copied()
+    })
+    .map(str::to_owned)
+}
+
+/// Debian short names that are spelled differently in SPDX.
+/// Not including the GNU licenses, which are handled by [`gnu_to_spdx`].
+const DEBIAN_NAMES: &[(&str, &str)] = &[
+  ("Expat", "MIT"),
+  ("BSD-2-clause", "BSD-2-Clause"),
+  ("BSD-3-clause", "BSD-3-Clause"),
+  ("BSD-4-clause", "BSD-4-Clause"),
+  ("Apache-1", "Apache-1.0"),
+  ("Apache-2", "Apache-2.0"),
+  ("Artistic", "Artistic-1.0"),
+  ("Artistic-1", "Artistic-1.0"),
+  ("Artistic-2", "Artistic-2.0"),
+  ("CC0", "CC0-1.0"),
+  ("EFL-1", "EFL-1.0"),
+  ("EFL-2", "EFL-2.0"),
+  ("MPL-1", "MPL-1.0"),
+  ("MPL-2", "MPL-2.0"),
+  ("Perl", "Artistic-1.0-Perl OR GPL-1.0-or-later"),
+  ("Python", "Python-2.0"),
+  ("Zope-1.1", "ZPL-1.1"),
+  ("Zope-2.0", "ZPL-2.0"),
+  ("Zope-2.1", "ZPL-2.1"),
+];
+
+/// SPDX identifiers that show up in Debian copyright files as-is.
+const SPDX_IDS: &[&str] = &[
+  "0BSD",
+  "AFL-3.0",
+  "Apache-1.0",
+  "Apache-1.1",
+  "Apache-2.0",
+  "Artistic-1.0",
+  "Artistic-1.0-Perl",
+  "Artistic-2.0",
+  "Beerware",
+  "BlueOak-1.0.0",
+  "BSD-1-Clause",
+  "BSD-2-Clause",
+  "BSD-2-Clause-Patent",
+  "BSD-3-Clause",
+  "BSD-4-Clause",
+  "BSL-1.0",
+  "bzip2-1.0.6",
+  "CC0-1.0",
+  "CDDL-1.0",
+  "CDDL-1.1",
+  "CECILL-2.1",
+  "CPL-1.0",
+  "curl",
+  "ECL-2.0",
+  "EFL-1.0",
+  "EFL-2.0",
+  "EPL-1.0",
+  "EPL-2.0",
+  "EUPL-1.2",
+  "FSFAP",
+  "FSFUL",
+  "FSFULLR",
+  "FTL",
+  "HPND",
+  "ICU",
+  "IJG",
+  "ISC",
+  "Libpng",
+  "libpng-2.0",
+  "LPPL-1.0",
+  "LPPL-1.1",
+  "LPPL-1.2",
+  "LPPL-1.3a",
+  "LPPL-1.3c",
+  "MIT",
+  "MIT-0",
+  "MPL-1.0",
+  "MPL-1.1",
+  "MPL-2.0",
+  "MS-PL",
+  "NCSA",
+  "OFL-1.0",
+  "OFL-1.1",
+  "OpenSSL",
+  "PHP-3.01",
+  "PostgreSQL",
+  "PSF-2.0",
+  "Python-2.0",
+  "QPL-1.0",
+  "Ruby",
+  "Sleepycat",
+  "TCL",
+  "Unicode-3.0",
+  "Unicode-DFS-2016",
+  "Unlicense",
+  "UPL-1.0",
+  "Vim",
+  "W3C",
+  "WTFPL",
+  "X11",
+  "Zlib",
+  "ZPL-1.1",
+  "ZPL-2.0",
+  "ZPL-2.1",
+];
+
+/// DEP-5 style exception names, ie `GPL-3+ with Font exception`.
+const DEBIAN_EXCEPTIONS: &[(&str, &str)] = &[
+  ("Autoconf", "Autoconf-exception-3.0"),
+  ("Bison", "Bison-exception-2.2"),
+  ("Classpath", "Classpath-exception-2.0"),
+  ("Font", "Font-exception-2.0"),
+  ("GCC", "GCC-exception-3.1"),
+  ("Libtool", "Libtool-exception"),
+  ("LLVM", "LLVM-exception"),
+  ("Qt", "Qt-LGPL-exception-1.1"),
+];
+
+const SPDX_EXCEPTIONS: &[&str] = &[
+  "Autoconf-exception-2.0",
+  "Autoconf-exception-3.0",
+  "Bison-exception-2.2",
+  "Bootloader-exception",
+  "Classpath-exception-2.0",
+  "Font-exception-2.0",
+  "GCC-exception-2.0",
+  "GCC-exception-3.1",
+  "Libtool-exception",
+  "Linux-syscall-note",
+  "LLVM-exception",
+  "OCaml-LGPL-linking-exception",
+  "OpenJDK-assembly-exception-1.0",
+  "Qt-GPL-exception-1.0",
+  "Qt-LGPL-exception-1.1",
+  "Swift-exception",
+  "u-boot-exception-2.0",
+  "WxWindows-exception-3.1",
+];
+
+// Parsing.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'s> {
+  Word(&'s str),
+  Comma,
+  Open,
+  Close,
+}
+
+fn tokenize(s: &str) -> Vec<Token<'_>> {
+  s.split_ascii_whitespace()
+    .flat_map(|word| {
+      // Commas and brackets usually stick to the words next to them
+      let mut tokens = Vec::new();
+      let mut start = 0;
+      for (idx, c) in word.char_indices() {
+        let token = match c {
+          ',' => Token::Comma,
+          '(' => Token::Open,
+          ')' => Token::Close,
+          _ => continue,
+        };
+        if start != idx {
+          tokens.push(Token::Word(&word[start..idx]));
+        }
+        tokens.push(token);
+        start = idx + 1;
+      }
+      if start != word.len() {
+        tokens.push(Token::Word(&word[start..]));
+      }
+      tokens
+    })
+    .collect()
+}
+
+fn is_keyword(token: Token<'_>, keyword: &str) -> bool {
+  matches!(token, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+}
+
+struct Parser<'s> {
+  tokens: Vec<Token<'s>>,
+  pos: usize,
+}
+
+impl<'s> Parser<'s> {
+  fn peek(&self) -> Option<Token<'s>> {
+    self.tokens.get(self.pos).copied()
+  }
+
+  fn eat_keyword(&mut self, keyword: &str) -> bool {
+    if self.peek().is_some_and(|tok| is_keyword(tok, keyword)) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  /// `group (, and|or group)*`
+  fn comma_expr(&mut self) -> eyre::Result<LicenseExpr> {
+    let mut out = self.or_expr()?;
+    while self.peek() == Some(Token::Comma) {
+      self.pos += 1;
+      let is_and = if self.eat_keyword("and") {
+        true
+      } else if self.eat_keyword("or") {
+        false
+      } else {
+        bail!("a comma must be followed by `and` or `or`");
+      };
+      out = combine(out, self.or_expr()?, is_and);
+    }
+    Ok(out)
+  }
+
+  fn or_expr(&mut self) -> eyre::Result<LicenseExpr> {
+    let mut out = self.and_expr()?;
+    while self.eat_keyword("or") {
+      out = combine(out, self.and_expr()?, false);
+    }
+    Ok(out)
+  }
+
+  fn and_expr(&mut self) -> eyre::Result<LicenseExpr> {
+    let mut out = self.group()?;
+    while self.eat_keyword("and") {
+      out = combine(out, self.group()?, true);
+    }
+    Ok(out)
+  }
+
+  /// `( comma_expr )` or a single license.
+  fn group(&mut self) -> eyre::Result<LicenseExpr> {
+    if self.peek() != Some(Token::Open) {
+      return self.with_expr();
+    }
+    self.pos += 1;
+    let out = self.comma_expr()?;
+    if self.peek() != Some(Token::Close) {
+      bail!("unclosed `(`");
+    }
+    self.pos += 1;
+    Ok(out)
+  }
+
+  fn with_expr(&mut self) -> eyre::Result<LicenseExpr> {
+    let name = match self.peek() {
+      Some(Token::Word(word))
+        if !["and", "or", "with"]
+          .iter()
+          .any(|kw| word.eq_ignore_ascii_case(kw)) =>
+      {
+        self.pos += 1;
+        word.to_owned()
+      }
+      Some(tok) => bail!("expected a license name, found {:?}", tok),
+      None => bail!("expected a license name, found the end"),
+    };
+
+    let exception = if self.eat_keyword("with") {
+      let mut words = Vec::new();
+      while let Some(Token::Word(word)) = self.peek() {
+        if is_keyword(Token::Word(word), "and")
+          || is_keyword(Token::Word(word), "or")
+        {
+          break;
+        }
+        words.push(word);
+        self.pos += 1;
+      }
+      // `with OpenSSL exception` -> `OpenSSL`
+      if words.len() > 1
+        && words
+          .last()
+          .is_some_and(|w| w.eq_ignore_ascii_case("exception"))
+      {
+        words.pop();
+      }
+      if words.is_empty() {
+        bail!("`with` must be followed by an exception name");
+      }
+      Some(words.join(" "))
+    } else {
+      None
+    };
+
+    Ok(LicenseExpr::License(LicenseName { name, exception }))
+  }
+}
+
+/// Join two expressions, flattening `a or b or c` into one list.
+fn combine(lhs: LicenseExpr, rhs: LicenseExpr, is_and: bool) -> LicenseExpr {
+  let mut children = Vec::new();
+  for expr in [lhs, rhs] {
+    match expr {
+      LicenseExpr::And(inner) if is_and => children.extend(inner),
+      LicenseExpr::Or(inner) if !is_and => children.extend(inner),
+      other => children.push(other),
+    }
+  }
+  if is_and {
+    LicenseExpr::And(children)
+  } else {
+    LicenseExpr::Or(children)
+  }
+}
+
+impl FromStr for LicenseExpr {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parser = Parser {
+      tokens: tokenize(s),
+      pos: 0,
+    };
+    let expr = parser
+      .comma_expr()
+      .map_err(|err| err.wrap_err(eyre!("in license expression {:?}", s)))?;
+    if let Some(tok) = parser.peek() {
+      bail!("unexpected {:?} in license expression {:?}", tok, s);
+    }
+    Ok(expr)
+  }
+}
+
+impl std::fmt::Display for LicenseName {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.name)?;
+    if let Some(exception) = &self.exception {
+      // Put back the `exception` that parsing took off
+      if exception.to_ascii_lowercase().contains("exception") {
+        write!(f, " with {}", exception)?;
+      } else {
+        write!(f, " with {} exception", exception)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Writes the expression back out in Debian syntax, with brackets where
+/// commas aren't enough.
+impl std::fmt::Display for LicenseExpr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (children, op) = match self {
+      LicenseExpr::License(name) => return std::fmt::Display::fmt(name, f),
+      LicenseExpr::And(children) => (children, "and"),
+      LicenseExpr::Or(children) => (children, "or"),
+    };
+    let sep = if self.needs_comma() {
+      format!(", {} ", op)
+    } else {
+      format!(" {} ", op)
+    };
+    for (idx, child) in children.iter().enumerate() {
+      if idx != 0 {
+        f.write_str(&sep)?;
+      }
+      if Self::needs_brackets(idx, child) {
+        write!(f, "({})", child)?;
+      } else {
+        std::fmt::Display::fmt(child, f)?;
+      }
+    }
+    Ok(())
+  }
+}
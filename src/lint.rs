@@ -0,0 +1,418 @@
+//! Check a `debian/copyright` file against the copyright format spec.
+//!
+//! The normal parser is deliberately forgiving, so that stripping works on
+//! files that aren't quite right. This is the strict counterpart.
+
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
+
+use clap::Args;
+use eyre::{Context, eyre};
+
+use crate::{
+  deb822::{Deb822File, DuplicateFields, Stanza, copyright},
+  glob::Glob,
+  span::{self, Line, Span, SpannedError},
+};
+
+const HEADER_FIELDS: &[&str] = &[
+  "Format",
+  "Upstream-Name",
+  "Upstream-Contact",
+  "Source",
+  "Disclaimer",
+  "Comment",
+  "License",
+  "Copyright",
+  "Files-Excluded",
+  "Files-Included",
+];
+/// Header fields that are [`FieldKind::Simple`](crate::deb822::FieldKind).
+const SIMPLE_FIELDS: &[&str] = &["Format", "Upstream-Name"];
+const FILES_FIELDS: &[&str] = &["Files", "Copyright", "License", "Comment"];
+const LICENSE_FIELDS: &[&str] = &["License", "Comment"];
+
+/// Check the copyright file for problems.
+///
+/// Exits with code `2` if there were any errors. Warnings alone don't
+/// change the exit code.
+#[derive(Args)]
+pub struct Lint {
+  /// Path to the debian copyright file.
+  /// [default: ./debian/copyright]
+  #[arg(short, long)]
+  debfile: Option<PathBuf>,
+}
+
+impl Lint {
+  pub fn do_it(self) -> eyre::Result<()> {
+    let path = self
+      .debfile
+      .unwrap_or(PathBuf::from(copyright::DEFAULT_PATH));
+    let source = std::fs::read_to_string(&path).wrap_err_with(|| {
+      eyre!("could not read copyright file at {}", path.display())
+    })?;
+
+    let diagnostics = lint(&source);
+    for diagnostic in &diagnostics {
+      println!("{}:{}", path.display(), diagnostic);
+      if let Some(span) = diagnostic.span {
+        let snippet = SpannedError::new(&source, span, &diagnostic.message);
+        println!("{}", snippet.snippet());
+      }
+    }
+    if diagnostics
+      .iter()
+      .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+      std::process::exit(2);
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+/// One problem found in the file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  /// Where the problem is, if it's anywhere in particular.
+  pub span: Option<Span>,
+  pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.span {
+      Some(span) => write!(f, "{}: ", span)?,
+      None => f.write_str("?:?: ")?,
+    }
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, "{}: {}", severity, &self.message)
+  }
+}
+
+/// Check `source` as a copyright file, and return everything wrong with it.
+///
+/// If the file is not valid deb822 at all, only the syntax errors are
+/// reported, but all of them.
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+  // The parser would only complain about a missing `:`
+  let is_blank = |line: &str| {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+  };
+  if source.lines().all(is_blank) {
+    return vec![Diagnostic {
+      severity: Severity::Error,
+      span: span::lines(source).next().map(|line| line.span_at(0, 0)),
+      message: "file is empty".into(),
+    }];
+  }
+
+  let (file, errors) =
+    Deb822File::parse_recovering(source, DuplicateFields::Error);
+  if !errors.is_empty() {
+    return errors
+      .into_iter()
+      .map(|error| Diagnostic {
+        severity: Severity::Error,
+        span: Some(error.span),
+        message: error.message,
+      })
+      .collect();
+  }
+
+  let mut linter = Linter { out: Vec::new() };
+  let stanzas: Vec<RawStanza> = file.stanzas().iter().map(raw_stanza).collect();
+  linter.lint_stanzas(&stanzas);
+  linter.out
+}
+
+/// A field, with the positions of its parts.
+struct RawField<'s> {
+  name: Line<'s>,
+  /// The value on the header line, if any, then the continuation lines.
+  /// All are trimmed, and ` .` lines are empty.
+  lines: Vec<Line<'s>>,
+  /// Whether there are any continuation lines.
+  multiline: bool,
+}
+
+impl<'s> RawField<'s> {
+  fn words(&self) -> impl Iterator<Item = Line<'s>> + '_ {
+    self.lines.iter().flat_map(|line| line.words())
+  }
+}
+
+type RawStanza<'s> = Vec<RawField<'s>>;
+
+fn get<'a, 's>(
+  stanza: &'a RawStanza<'s>,
+  name: &str,
+) -> Option<&'a RawField<'s>> {
+  stanza
+    .iter()
+    .find(|field| field.name.text.eq_ignore_ascii_case(name))
+}
+
+/// Pair up the names and values of a parsed stanza with their positions.
+fn raw_stanza<'s>(stanza: &'s Stanza<'_>) -> RawStanza<'s> {
+  stanza
+    .iter()
+    .map(|(name, field)| RawField {
+      name: Line::at(name, field.span.unwrap_or_default()),
+      lines: field
+        .iter_lines()
+        .zip(&field.value_spans)
+        .map(|(text, span)| Line::at(text, *span).trim())
+        .collect(),
+      multiline: !field.list_values.is_empty(),
+    })
+    .collect()
+}
+
+struct Linter {
+  out: Vec<Diagnostic>,
+}
+
+impl Linter {
+  fn report(&mut self, severity: Severity, at: Line<'_>, message: String) {
+    self.out.push(Diagnostic {
+      severity,
+      span: Some(at.span()),
+      message,
+    });
+  }
+
+  fn lint_stanzas<'s>(&mut self, stanzas: &[RawStanza<'s>]) {
+    let rest = match stanzas.split_first() {
+      Some((first, _)) if get(first, "Files").is_some() => {
+        self.report(
+          Severity::Error,
+          first[0].name,
+          "file has no header paragraph before the first Files paragraph"
+            .into(),
+        );
+        stanzas
+      }
+      Some((header, rest)) => {
+        self.lint_header(header);
+        rest
+      }
+      // `lint` already reported the file as empty
+      None => return,
+    };
+
+    let mut files_globs = HashMap::new();
+    for stanza in rest {
+      if get(stanza, "Files").is_some() {
+        self.check_field_names(stanza, FILES_FIELDS, "Files");
+        for required in ["Copyright", "License"] {
+          if get(stanza, required).is_none() {
+            self.report(
+              Severity::Error,
+              stanza[0].name,
+              format!("Files paragraph has no `{}` field", required),
+            );
+          }
+        }
+        if let Some(files) = get(stanza, "Files") {
+          self.check_globs(files, &mut files_globs);
+        }
+      } else if get(stanza, "License").is_some() {
+        self.check_field_names(stanza, LICENSE_FIELDS, "License");
+      } else {
+        self.report(
+          Severity::Error,
+          stanza[0].name,
+          "paragraph has neither a `Files` nor a `License` field".into(),
+        );
+      }
+    }
+
+    self.check_licenses(stanzas);
+  }
+
+  fn lint_header<'s>(&mut self, header: &RawStanza<'s>) {
+    match get(header, "Format") {
+      None => self.report(
+        Severity::Error,
+        header[0].name,
+        "header paragraph has no `Format` field".into(),
+      ),
+      Some(format) => {
+        if !format
+          .lines
+          .iter()
+          .any(|l| l.text.contains("copyright-format/1.0"))
+        {
+          self.report(
+            Severity::Warning,
+            format.name,
+            format!("`Format` should be {}", copyright::FORMAT_URL),
+          );
+        }
+      }
+    }
+    self.check_field_names(header, HEADER_FIELDS, "header");
+    for name in SIMPLE_FIELDS {
+      if let Some(field) = get(header, name)
+        && field.multiline
+      {
+        self.report(
+          Severity::Warning,
+          field.name,
+          format!("`{}` should be on one line", name),
+        );
+      }
+    }
+
+    let mut excluded_globs = HashMap::new();
+    for name in ["Files-Excluded", "Files-Included"] {
+      if let Some(field) = get(header, name) {
+        self.check_globs(field, &mut excluded_globs);
+      }
+    }
+  }
+
+  fn check_field_names<'s>(
+    &mut self,
+    stanza: &RawStanza<'s>,
+    allowed: &[&str],
+    kind: &str,
+  ) {
+    for field in stanza {
+      if let Some(known) = allowed
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(field.name.text))
+      {
+        if *known != field.name.text {
+          self.report(
+            Severity::Warning,
+            field.name,
+            format!(
+              "field `{}` is usually spelled `{}`",
+              field.name.text, known
+            ),
+          );
+        }
+        continue;
+      }
+      let lower = field.name.text.to_ascii_lowercase();
+      if lower.starts_with("files-excluded")
+        || lower.starts_with("files-included")
+      {
+        self.report(
+          Severity::Error,
+          field.name,
+          format!("`{}` must be in the header paragraph", field.name.text),
+        );
+      } else if !lower.starts_with("x-") {
+        self.report(
+          Severity::Warning,
+          field.name,
+          format!("unknown field `{}` in {} paragraph", field.name.text, kind),
+        );
+      }
+    }
+  }
+
+  /// Check that the globs parse, and that none appear twice.
+  fn check_globs<'s>(
+    &mut self,
+    field: &RawField<'s>,
+    seen: &mut HashMap<&'s str, Span>,
+  ) {
+    for word in field.words() {
+      if let Err(err) = Glob::from_str(word.text) {
+        self.report(
+          Severity::Error,
+          word,
+          format!("unsupported glob `{}`: {}", word.text, err),
+        );
+      }
+      if let Some(first) = seen.get(word.text) {
+        let message =
+          format!("duplicate glob `{}` (first seen at {})", word.text, first);
+        self.report(Severity::Warning, word, message);
+      } else {
+        seen.insert(word.text, word.span());
+      }
+    }
+  }
+
+  /// Check that every license that's only given by name has its text
+  /// somewhere.
+  fn check_licenses<'s>(&mut self, stanzas: &[RawStanza<'s>]) {
+    let licenses: Vec<&RawField> = stanzas
+      .iter()
+      .filter_map(|stanza| get(stanza, "License"))
+      .collect();
+    let defined: Vec<&str> = licenses
+      .iter()
+      .filter(|license| license.multiline)
+      .filter_map(|license| license.lines.first().map(|line| line.text))
+      .chain(
+        stanzas
+          .iter()
+          .skip(1)
+          .filter(|stanza| get(stanza, "Files").is_none())
+          .filter_map(|stanza| get(stanza, "License"))
+          .filter_map(|license| license.lines.first().map(|line| line.text)),
+      )
+      .collect();
+
+    for license in licenses.iter().filter(|license| !license.multiline) {
+      let Some(expr) = license.lines.first() else {
+        self.report(
+          Severity::Error,
+          license.name,
+          "`License` field is empty".into(),
+        );
+        continue;
+      };
+      for name in short_names(expr) {
+        if !defined.contains(&name.text) {
+          self.report(
+            Severity::Error,
+            name,
+            format!(
+              "license `{}` has no standalone License paragraph",
+              name.text
+            ),
+          );
+        }
+      }
+    }
+  }
+}
+
+/// Pick the license short names out of a license expression like
+/// `GPL-2+ or Artistic-2.0, and BSD-3-clause with Foo exception`.
+fn short_names<'s>(expr: &Line<'s>) -> Vec<Line<'s>> {
+  let mut names = Vec::new();
+  let mut start = 0;
+  let ends = expr.text.match_indices(',').map(|(idx, _)| idx);
+  for end in ends.chain([expr.text.len()]) {
+    let part = expr.slice(start, end);
+    start = end + 1;
+    let mut in_exception = false;
+    for word in part.words() {
+      match word.text {
+        "or" | "and" => in_exception = false,
+        "with" => in_exception = true,
+        _ if !in_exception => names.push(word),
+        _ => {}
+      }
+    }
+  }
+  names
+}
@@ -1,11 +1,21 @@
 use std::{path::PathBuf, str::FromStr};
 
-use clap::{Parser, Subcommand, command};
+use clap::{Parser, Subcommand};
 
+#[cfg(feature = "serde")]
+use deb_strip_copyright::convert::Convert;
 use deb_strip_copyright::{
-  deb822::{Deb822File, copyright::CopyrightFile},
+  changelog::Changelog,
+  coverage::Coverage,
+  deb822::{
+    Deb822File, DuplicateFields,
+    copyright::{self, CopyrightFile},
+    signed::SignedFile,
+  },
   glob::Glob,
+  lint::Lint,
   strip::Strip,
+  survey::Survey,
 };
 
 /// A (WIP incomplete) replacement for mk-origtargz.
@@ -37,6 +47,33 @@ struct Cli {
 enum Subcommands {
   #[command(name = "strip")]
   Strip(Strip),
+  #[command(name = "coverage")]
+  Coverage(Coverage),
+  #[command(name = "lint")]
+  Lint(Lint),
+  #[command(name = "survey")]
+  Survey(Survey),
+  #[cfg(feature = "serde")]
+  #[command(name = "convert")]
+  Convert(Convert),
+  /// Print which license applies to files, according to the
+  /// `debian/copyright` file.
+  ///
+  /// If several `Files` paragraphs match a path, the last one wins.
+  /// Exits with code `2` if any path is not matched by any paragraph.
+  #[command(name = "which-license")]
+  WhichLicense {
+    /// Paths to look up, relative to the root of the source tree.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Path to the debian copyright file.
+    /// [default: ./debian/copyright]
+    #[arg(short, long)]
+    debfile: Option<PathBuf>,
+    /// Print the whole `Files` paragraph, not just the license name.
+    #[arg(short, long)]
+    full: bool,
+  },
   #[command(name = "debugs", subcommand)]
   DebugSubcommands(DebugSubcommands),
 }
@@ -48,12 +85,30 @@ enum DebugSubcommands {
   /// Parse a file in Deb822 format, and dump the AST to stdout.
   /// This is mostly for debugging.
   #[command(name = "parse-deb")]
-  ParseDeb822 { path: PathBuf },
+  ParseDeb822 {
+    path: PathBuf,
+    /// Keep fields that appear more than once in a stanza, instead of
+    /// failing.
+    #[arg(long)]
+    keep_duplicates: bool,
+    /// If the file is clearsigned, check the signature against the keys
+    /// in this keyring first.
+    #[arg(long, value_name = "FILE")]
+    keyring: Option<PathBuf>,
+    /// Carry on past syntax errors, print all of them to stderr, and
+    /// dump what did parse. Exits with code `2` if there were any.
+    #[arg(long)]
+    all_errors: bool,
+  },
   /// Parse a file in Deb822 format, collect the data into specialized
   /// `debian/copyright` format, and dump the AST to stdout.
   /// This is mostly for debugging.
   #[command(name = "parse-copyright")]
   ParseCopyright { path: PathBuf },
+  /// Parse a `debian/changelog` file, and dump the AST to stdout.
+  /// This is mostly for debugging.
+  #[command(name = "parse-changelog")]
+  ParseChangelog { path: PathBuf },
   /// Parse a simplified Debian glob, and dump the AST or test it on
   /// a string.
   #[command(name = "glob")]
@@ -81,17 +136,89 @@ fn main() -> eyre::Result<()> {
     Subcommands::Strip(strip) => {
       strip.do_it()?;
     }
+    Subcommands::Coverage(coverage) => {
+      coverage.do_it()?;
+    }
+    Subcommands::Lint(lint) => {
+      lint.do_it()?;
+    }
+    Subcommands::Survey(survey) => {
+      survey.do_it()?;
+    }
+    #[cfg(feature = "serde")]
+    Subcommands::Convert(convert) => {
+      convert.do_it()?;
+    }
+    Subcommands::WhichLicense {
+      paths,
+      debfile,
+      full,
+    } => {
+      let copyright = CopyrightFile::from_path(
+        debfile.unwrap_or(PathBuf::from(copyright::DEFAULT_PATH)),
+      )?;
+      let mut all_found = true;
+      for path in &paths {
+        let Some(paragraph) = copyright.lookup(path) else {
+          println!("{}: <no Files paragraph>", path.display());
+          all_found = false;
+          continue;
+        };
+        let license = paragraph
+          .license
+          .as_ref()
+          .map_or("<no License field>", |license| &license.name);
+        println!("{}: {}", path.display(), license);
+        if full {
+          println!("{}", paragraph);
+        }
+      }
+      if !all_found {
+        std::process::exit(2);
+      }
+    }
     Subcommands::DebugSubcommands(dbg) => match dbg {
-      DebugSubcommands::ParseDeb822 { path } => {
+      DebugSubcommands::ParseDeb822 {
+        path,
+        keep_duplicates,
+        keyring,
+        all_errors,
+      } => {
         let file = std::fs::read_to_string(path)?;
-        let ast = Deb822File::from_str(&file)?;
-        println!("{:#?}", &ast);
+        if let Some(keyring) = keyring {
+          let signed = SignedFile::from_str(&file)?;
+          let fingerprint = signed.verify(keyring)?;
+          eprintln!("good signature from {}", fingerprint);
+        }
+        let duplicates = if keep_duplicates {
+          DuplicateFields::Keep
+        } else {
+          DuplicateFields::Error
+        };
+        if all_errors {
+          let (ast, errors) = Deb822File::parse_recovering(&file, duplicates);
+          println!("{:#?}", &ast);
+          for error in &errors {
+            eprintln!("error: {}\n", error);
+          }
+          if !errors.is_empty() {
+            std::process::exit(2);
+          }
+        } else {
+          let ast = Deb822File::parse(&file, duplicates)?;
+          println!("{:#?}", &ast);
+        }
       }
       DebugSubcommands::ParseCopyright { path } => {
         let file = std::fs::read_to_string(path)?;
         let ast = CopyrightFile::from_str(&file)?;
         println!("{:#?}", &ast);
       }
+      DebugSubcommands::ParseChangelog { path } => {
+        let file = std::fs::read_to_string(path)?;
+        let ast = Changelog::from_str(&file)?;
+        println!("{:#?}", &ast);
+      }
       DebugSubcommands::ParseGlob { glob, dump, test } => {
         let glob = Glob::from_str(&glob)?;
         if dump {
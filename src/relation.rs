@@ -0,0 +1,400 @@
+//! Parse package relationship fields, like `Depends` and `Build-Depends`.
+//!
+//! The syntax is in the "Declaring relationships between packages"
+//! chapter of Debian policy:
+//! https://www.debian.org/doc/debian-policy/ch-relationships
+//!
+//! Commas separate relations that all have to be satisfied, and `|`
+//! separates alternatives inside one of them. Each package name can have
+//! an architecture qualifier, a version constraint, an architecture
+//! restriction list, and build profile restriction formulas, in that
+//! order:
+//! `foo:any (>= 1.0) [amd64 !i386] <!nocheck> <stage1 cross>`.
+//!
+//! Substitution variables like `${misc:Depends}` are kept as names,
+//! so that `debian/control` files can be parsed before substitution.
+
+use std::str::FromStr;
+
+use eyre::{bail, eyre};
+use log::warn;
+
+/// A whole relation field. Every entry has to be satisfied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Relations(pub Vec<Alternatives>);
+
+/// One comma-separated entry. Any one of the relations will do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alternatives(pub Vec<Relation>);
+
+/// A relation on one package, like `foo:any (>= 1.0) [amd64] <!nocheck>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+  pub name: String,
+  /// The part after `:`, like `any` or `native`.
+  pub arch_qualifier: Option<String>,
+  pub version: Option<VersionConstraint>,
+  /// The architectures in `[...]`. If any is negated they all are.
+  pub architectures: Vec<ArchRestriction>,
+  /// Each `<...>` is one formula. The relation applies if any formula
+  /// matches, and a formula matches if all its terms do.
+  pub profiles: Vec<Vec<ProfileTerm>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+  pub op: VersionOp,
+  pub version: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOp {
+  /// `<<`
+  Earlier,
+  /// `<=`
+  EarlierOrEqual,
+  /// `=`
+  Equal,
+  /// `>=`
+  LaterOrEqual,
+  /// `>>`
+  Later,
+}
+
+/// One architecture in `[...]`, like `amd64` or `!i386`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchRestriction {
+  pub negated: bool,
+  pub arch: String,
+}
+
+/// One build profile in `<...>`, like `nocheck` or `!cross`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileTerm {
+  pub negated: bool,
+  pub name: String,
+}
+
+impl Relations {
+  pub fn iter(&self) -> impl Iterator<Item = &Alternatives> + '_ {
+    self.0.iter()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  /// Every package relation, in all the alternatives, left to right.
+  pub fn relations(&self) -> impl Iterator<Item = &Relation> + '_ {
+    self.0.iter().flat_map(|alternatives| alternatives.0.iter())
+  }
+}
+
+impl VersionOp {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      VersionOp::Earlier => "<<",
+      VersionOp::EarlierOrEqual => "<=",
+      VersionOp::Equal => "=",
+      VersionOp::LaterOrEqual => ">=",
+      VersionOp::Later => ">>",
+    }
+  }
+}
+
+// Parsing.
+// This works on characters directly, because whitespace is optional
+// almost everywhere: `foo(>=1.0)[amd64]` is the same as
+// `foo (>= 1.0) [amd64]`.
+
+/// Characters that can't be in a package name (or architecture or
+/// profile name).
+const NAME_END: &[char] = &[
+  ' ', '\t', '\n', ',', '|', '(', ')', '[', ']', '<', '>', ':', '!',
+];
+
+struct Parser<'s> {
+  rest: &'s str,
+}
+
+impl<'s> Parser<'s> {
+  fn skip_ws(&mut self) {
+    self.rest = self.rest.trim_start();
+  }
+
+  fn peek(&mut self) -> Option<char> {
+    self.skip_ws();
+    self.rest.chars().next()
+  }
+
+  /// Eat `c` if it's next.
+  fn eat(&mut self, c: char) -> bool {
+    if self.peek() == Some(c) {
+      self.rest = &self.rest[c.len_utf8()..];
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, c: char) -> eyre::Result<()> {
+    if !self.eat(c) {
+      bail!("expected `{}` at {:?}", c, self.rest);
+    }
+    Ok(())
+  }
+
+  fn name(&mut self, what: &str) -> eyre::Result<&'s str> {
+    self.skip_ws();
+    let end = if self.rest.starts_with("${") {
+      // Substitution variables have `:` in them
+      self
+        .rest
+        .find('}')
+        .map(|idx| idx + 1)
+        .ok_or_else(|| eyre!("unclosed substitution variable"))?
+    } else {
+      self.rest.find(NAME_END).unwrap_or(self.rest.len())
+    };
+    if end == 0 {
+      bail!("expected {} at {:?}", what, self.rest);
+    }
+    let (name, rest) = self.rest.split_at(end);
+    self.rest = rest;
+    Ok(name)
+  }
+
+  fn relations(&mut self) -> eyre::Result<Relations> {
+    let mut out = Vec::new();
+    loop {
+      // Empty entries, like after a trailing comma, are allowed
+      match self.peek() {
+        None => break,
+        Some(',') => {
+          self.eat(',');
+          continue;
+        }
+        Some(_) => {}
+      }
+      out.push(self.alternatives()?);
+      match self.peek() {
+        None => break,
+        Some(',') => {}
+        Some(_) => bail!("expected `,` at {:?}", self.rest),
+      }
+    }
+    Ok(Relations(out))
+  }
+
+  fn alternatives(&mut self) -> eyre::Result<Alternatives> {
+    let mut out = vec![self.relation()?];
+    while self.eat('|') {
+      out.push(self.relation()?);
+    }
+    Ok(Alternatives(out))
+  }
+
+  fn relation(&mut self) -> eyre::Result<Relation> {
+    let name = self.name("a package name")?.to_owned();
+    // The qualifier has to be right after the name, no spaces
+    let arch_qualifier = match self.rest.strip_prefix(':') {
+      Some(rest) => {
+        self.rest = rest;
+        Some(self.name("an architecture qualifier")?.to_owned())
+      }
+      None => None,
+    };
+
+    let version = if self.eat('(') {
+      let op = self.version_op()?;
+      self.skip_ws();
+      let end = self.rest.find([')', ' ', '\t', '\n']).unwrap_or(0);
+      if end == 0 {
+        bail!("expected a version at {:?}", self.rest);
+      }
+      let (version, rest) = self.rest.split_at(end);
+      self.rest = rest;
+      self.expect(')')?;
+      Some(VersionConstraint {
+        op,
+        version: version.to_owned(),
+      })
+    } else {
+      None
+    };
+
+    let mut architectures = Vec::new();
+    if self.eat('[') {
+      while !self.eat(']') {
+        let negated = self.eat('!');
+        let arch = self.name("an architecture")?.to_owned();
+        architectures.push(ArchRestriction { negated, arch });
+      }
+      if architectures.is_empty() {
+        bail!("empty architecture restriction list in {:?}", name);
+      }
+    }
+
+    let mut profiles = Vec::new();
+    while self.eat('<') {
+      let mut formula = Vec::new();
+      while !self.eat('>') {
+        let negated = self.eat('!');
+        let name = self.name("a build profile")?.to_owned();
+        formula.push(ProfileTerm { negated, name });
+      }
+      if formula.is_empty() {
+        bail!("empty build profile formula in {:?}", name);
+      }
+      profiles.push(formula);
+    }
+
+    Ok(Relation {
+      name,
+      arch_qualifier,
+      version,
+      architectures,
+      profiles,
+    })
+  }
+
+  fn version_op(&mut self) -> eyre::Result<VersionOp> {
+    self.skip_ws();
+    let ops = [
+      ("<<", VersionOp::Earlier),
+      ("<=", VersionOp::EarlierOrEqual),
+      (">=", VersionOp::LaterOrEqual),
+      (">>", VersionOp::Later),
+      ("=", VersionOp::Equal),
+      // Obsolete, but dpkg still reads them like this
+      ("<", VersionOp::EarlierOrEqual),
+      (">", VersionOp::LaterOrEqual),
+    ];
+    for (text, op) in ops {
+      if let Some(rest) = self.rest.strip_prefix(text) {
+        if text.len() == 1 && text != "=" {
+          warn!(
+            "obsolete version relation `{}`, reading it as `{}`",
+            text,
+            op.as_str()
+          );
+        }
+        self.rest = rest;
+        return Ok(op);
+      }
+    }
+    bail!("expected a version relation at {:?}", self.rest)
+  }
+}
+
+impl FromStr for Relations {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parser = Parser { rest: s };
+    parser
+      .relations()
+      .map_err(|err| err.wrap_err(eyre!("in relation field {:?}", s)))
+  }
+}
+
+impl FromStr for Relation {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parser = Parser { rest: s };
+    let relation = parser
+      .relation()
+      .map_err(|err| err.wrap_err(eyre!("in relation {:?}", s)))?;
+    if parser.peek().is_some() {
+      bail!("unexpected {:?} after relation {:?}", parser.rest, s);
+    }
+    Ok(relation)
+  }
+}
+
+// Writing. This is the normal spacing, like `foo (>= 1.0) [amd64]`.
+
+/// Writes entries separated by `, `.
+impl std::fmt::Display for Relations {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (idx, alternatives) in self.0.iter().enumerate() {
+      if idx != 0 {
+        f.write_str(", ")?;
+      }
+      std::fmt::Display::fmt(alternatives, f)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::fmt::Display for Alternatives {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (idx, relation) in self.0.iter().enumerate() {
+      if idx != 0 {
+        f.write_str(" | ")?;
+      }
+      std::fmt::Display::fmt(relation, f)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::fmt::Display for Relation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.name)?;
+    if let Some(qualifier) = &self.arch_qualifier {
+      write!(f, ":{}", qualifier)?;
+    }
+    if let Some(version) = &self.version {
+      write!(f, " ({} {})", version.op.as_str(), &version.version)?;
+    }
+    if !self.architectures.is_empty() {
+      f.write_str(" [")?;
+      for (idx, arch) in self.architectures.iter().enumerate() {
+        if idx != 0 {
+          f.write_str(" ")?;
+        }
+        if arch.negated {
+          f.write_str("!")?;
+        }
+        f.write_str(&arch.arch)?;
+      }
+      f.write_str("]")?;
+    }
+    for formula in &self.profiles {
+      f.write_str(" <")?;
+      for (idx, term) in formula.iter().enumerate() {
+        if idx != 0 {
+          f.write_str(" ")?;
+        }
+        if term.negated {
+          f.write_str("!")?;
+        }
+        f.write_str(&term.name)?;
+      }
+      f.write_str(">")?;
+    }
+    Ok(())
+  }
+}
+
+/// Relations are written as their source text.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Relations {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(self)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Relations {
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    let s = String::deserialize(d)?;
+    Relations::from_str(&s).map_err(serde::de::Error::custom)
+  }
+}
@@ -0,0 +1,423 @@
+//! Write an SPDX 2.3 document describing the stripped tarball.
+//!
+//! Every kept file is listed with its checksums, and the copyright and
+//! license of the `Files` paragraph that governs it.
+//! See https://spdx.github.io/spdx-spec/v2.3/
+
+use std::{collections::BTreeMap, io::Write};
+
+use clap::ValueEnum;
+use log::warn;
+use serde_json::json;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+  deb822::copyright::{CopyrightFile, License},
+  strip::KeptFile,
+};
+
+const NOASSERTION: &str = "NOASSERTION";
+
+/// Which SPDX serialization to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+  /// The tag-value format.
+  #[value(name = "spdx")]
+  TagValue,
+  /// The JSON format.
+  #[value(name = "spdx-json")]
+  Json,
+}
+
+impl SbomFormat {
+  /// Guess the format from a file name: JSON if it ends in `.json`,
+  /// tag-value otherwise.
+  pub fn from_file_name(name: &str) -> Self {
+    if name.ends_with(".json") {
+      SbomFormat::Json
+    } else {
+      SbomFormat::TagValue
+    }
+  }
+}
+
+/// An SPDX document, describing one package.
+#[derive(Debug, Clone)]
+pub struct SpdxDocument {
+  pub name: String,
+  pub namespace: String,
+  /// Creation time, like `2024-01-31T12:00:00Z`.
+  pub created: String,
+  pub package: SpdxPackage,
+  pub files: Vec<SpdxFile>,
+  /// Licenses with no SPDX identifier, by their `LicenseRef-` id.
+  pub extracted_licenses: BTreeMap<String, ExtractedLicense>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpdxPackage {
+  pub name: String,
+  pub verification_code: String,
+  pub license_declared: String,
+  pub copyright_text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpdxFile {
+  /// The path, as `./foo/bar.c`.
+  pub name: String,
+  /// Lowercase hex.
+  pub sha1: String,
+  /// Lowercase hex.
+  pub sha256: String,
+  pub license_concluded: String,
+  pub copyright_text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtractedLicense {
+  /// The Debian short name.
+  pub name: String,
+  pub text: String,
+}
+
+impl SpdxDocument {
+  /// Describe `files` using the copyright file.
+  ///
+  /// `name` is the package name, which also names the document.
+  /// The creation time comes from `SOURCE_DATE_EPOCH` if it is set, so
+  /// that the output is reproducible.
+  pub fn new(
+    name: &str,
+    copyright: &CopyrightFile,
+    files: &[KeptFile],
+  ) -> Self {
+    let mut extracted_licenses = BTreeMap::new();
+    let mut license_of = |license: Option<&License>| {
+      let Some(license) = license else {
+        return NOASSERTION.to_string();
+      };
+      let expr = match license.expr() {
+        Ok(expr) => expr,
+        Err(err) => {
+          warn!("can't parse license {:?}: {:#}", &license.name, err);
+          return NOASSERTION.to_string();
+        }
+      };
+      for name in expr.names() {
+        if name.to_spdx().is_some() {
+          continue;
+        }
+        extracted_licenses
+          .entry(name.license_ref())
+          .or_insert_with(|| ExtractedLicense {
+            name: name.to_string(),
+            text: license_text(copyright, license, &name.name),
+          });
+      }
+      expr.to_spdx_lossy()
+    };
+
+    let files: Vec<SpdxFile> = files
+      .iter()
+      .map(|file| {
+        let paragraph = copyright.lookup(&file.path);
+        SpdxFile {
+          name: format!("./{}", file.path.to_string_lossy()),
+          sha1: hex(&file.sha1),
+          sha256: hex(&file.sha256),
+          license_concluded: license_of(
+            paragraph.and_then(|p| p.license.as_ref()),
+          ),
+          copyright_text: copyright_text(
+            paragraph.map_or(&[][..], |p| &p.copyright),
+          ),
+        }
+      })
+      .collect();
+
+    let package = SpdxPackage {
+      name: copyright
+        .header
+        .upstream_name
+        .clone()
+        .unwrap_or_else(|| name.to_string()),
+      verification_code: verification_code(&files),
+      license_declared: license_of(copyright.header.license.as_ref()),
+      copyright_text: copyright_text(&copyright.header.copyright),
+    };
+
+    // The namespace only has to be unique, so derive it from the contents
+    // to keep the output reproducible.
+    let mut hasher = Sha256::new();
+    hasher.update(name.as_bytes());
+    for file in &files {
+      hasher.update(file.name.as_bytes());
+      hasher.update(file.sha256.as_bytes());
+    }
+    let namespace = format!(
+      "https://spdx.org/spdxdocs/{}-{}",
+      name,
+      hex(&hasher.finalize())
+    );
+
+    SpdxDocument {
+      name: name.to_string(),
+      namespace,
+      created: creation_time(),
+      package,
+      files,
+      extracted_licenses,
+    }
+  }
+
+  /// Date the document at `secs` since the unix epoch, like the date of
+  /// the newest changelog entry, unless `SOURCE_DATE_EPOCH` is set.
+  pub fn with_fallback_time(mut self, secs: i64) -> Self {
+    if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+      self.created = format_utc(secs);
+    }
+    self
+  }
+
+  pub fn write<W: Write>(
+    &self,
+    format: SbomFormat,
+    mut out: W,
+  ) -> eyre::Result<()> {
+    match format {
+      SbomFormat::TagValue => self.write_tag_value(&mut out)?,
+      SbomFormat::Json => {
+        serde_json::to_writer_pretty(&mut out, &self.to_json())?;
+        writeln!(out)?;
+      }
+    }
+    Ok(())
+  }
+
+  pub fn write_tag_value<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+    writeln!(out, "SPDXVersion: SPDX-2.3")?;
+    writeln!(out, "DataLicense: CC0-1.0")?;
+    writeln!(out, "SPDXID: SPDXRef-DOCUMENT")?;
+    writeln!(out, "DocumentName: {}", self.name)?;
+    writeln!(out, "DocumentNamespace: {}", self.namespace)?;
+    writeln!(out, "Creator: {}", creator())?;
+    writeln!(out, "Created: {}", self.created)?;
+
+    let package = &self.package;
+    writeln!(out)?;
+    writeln!(out, "PackageName: {}", package.name)?;
+    writeln!(out, "SPDXID: SPDXRef-Package")?;
+    writeln!(out, "PackageDownloadLocation: {}", NOASSERTION)?;
+    writeln!(out, "FilesAnalyzed: true")?;
+    writeln!(
+      out,
+      "PackageVerificationCode: {}",
+      package.verification_code
+    )?;
+    writeln!(out, "PackageLicenseConcluded: {}", NOASSERTION)?;
+    writeln!(out, "PackageLicenseDeclared: {}", package.license_declared)?;
+    writeln!(
+      out,
+      "PackageCopyrightText: {}",
+      text_value(&package.copyright_text)
+    )?;
+    writeln!(
+      out,
+      "Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package"
+    )?;
+
+    for (idx, file) in self.files.iter().enumerate() {
+      writeln!(out)?;
+      writeln!(out, "FileName: {}", file.name)?;
+      writeln!(out, "SPDXID: {}", file_id(idx))?;
+      writeln!(out, "FileChecksum: SHA1: {}", file.sha1)?;
+      writeln!(out, "FileChecksum: SHA256: {}", file.sha256)?;
+      writeln!(out, "LicenseConcluded: {}", file.license_concluded)?;
+      writeln!(
+        out,
+        "FileCopyrightText: {}",
+        text_value(&file.copyright_text)
+      )?;
+      writeln!(
+        out,
+        "Relationship: SPDXRef-Package CONTAINS {}",
+        file_id(idx)
+      )?;
+    }
+
+    for (id, license) in &self.extracted_licenses {
+      writeln!(out)?;
+      writeln!(out, "LicenseID: {}", id)?;
+      writeln!(out, "LicenseName: {}", license.name)?;
+      writeln!(out, "ExtractedText: {}", text_value(&license.text))?;
+    }
+
+    Ok(())
+  }
+
+  pub fn to_json(&self) -> serde_json::Value {
+    let package = &self.package;
+    let mut relationships = vec![json!({
+      "spdxElementId": "SPDXRef-DOCUMENT",
+      "relationshipType": "DESCRIBES",
+      "relatedSpdxElement": "SPDXRef-Package",
+    })];
+    relationships.extend((0..self.files.len()).map(|idx| {
+      json!({
+        "spdxElementId": "SPDXRef-Package",
+        "relationshipType": "CONTAINS",
+        "relatedSpdxElement": file_id(idx),
+      })
+    }));
+
+    json!({
+      "spdxVersion": "SPDX-2.3",
+      "dataLicense": "CC0-1.0",
+      "SPDXID": "SPDXRef-DOCUMENT",
+      "name": self.name,
+      "documentNamespace": self.namespace,
+      "creationInfo": {
+        "created": self.created,
+        "creators": [creator()],
+      },
+      "packages": [{
+        "SPDXID": "SPDXRef-Package",
+        "name": package.name,
+        "downloadLocation": NOASSERTION,
+        "filesAnalyzed": true,
+        "packageVerificationCode": {
+          "packageVerificationCodeValue": package.verification_code,
+        },
+        "licenseConcluded": NOASSERTION,
+        "licenseDeclared": package.license_declared,
+        "copyrightText": package.copyright_text,
+      }],
+      "files": self.files.iter().enumerate().map(|(idx, file)| json!({
+        "SPDXID": file_id(idx),
+        "fileName": file.name,
+        "checksums": [
+          { "algorithm": "SHA1", "checksumValue": file.sha1 },
+          { "algorithm": "SHA256", "checksumValue": file.sha256 },
+        ],
+        "licenseConcluded": file.license_concluded,
+        "copyrightText": file.copyright_text,
+      })).collect::<Vec<_>>(),
+      "hasExtractedLicensingInfos": self
+        .extracted_licenses
+        .iter()
+        .map(|(id, license)| json!({
+          "licenseId": id,
+          "name": license.name,
+          "extractedText": license.text,
+        }))
+        .collect::<Vec<_>>(),
+      "relationships": relationships,
+    })
+  }
+}
+
+fn creator() -> String {
+  format!(
+    "Tool: {}-{}",
+    env!("CARGO_PKG_NAME"),
+    env!("CARGO_PKG_VERSION")
+  )
+}
+
+fn file_id(idx: usize) -> String {
+  format!("SPDXRef-File-{}", idx + 1)
+}
+
+/// Free text in tag-value has to be wrapped, unless it's `NOASSERTION`.
+fn text_value(text: &str) -> String {
+  if text == NOASSERTION {
+    text.to_string()
+  } else {
+    format!("<text>{}</text>", text)
+  }
+}
+
+fn copyright_text(lines: &[String]) -> String {
+  if lines.is_empty() {
+    NOASSERTION.to_string()
+  } else {
+    lines.join("\n")
+  }
+}
+
+/// Find the text of the license called `name`, which is part of the
+/// `License` field `license`.
+fn license_text(
+  copyright: &CopyrightFile,
+  license: &License,
+  name: &str,
+) -> String {
+  if let Some(paragraph) = copyright.license_text(name)
+    && !paragraph.license.text.is_empty()
+  {
+    return paragraph.license.text.join("\n");
+  }
+  if license.name == name && !license.text.is_empty() {
+    return license.text.join("\n");
+  }
+  format!(
+    "The {} license. Its text was not found in debian/copyright.",
+    name
+  )
+}
+
+/// SHA1 of the sorted SHA1s of every file, as the spec says.
+fn verification_code(files: &[SpdxFile]) -> String {
+  let mut sums: Vec<&str> =
+    files.iter().map(|file| file.sha1.as_str()).collect();
+  sums.sort_unstable();
+  let mut hasher = Sha1::new();
+  for sum in sums {
+    hasher.update(sum.as_bytes());
+  }
+  hex(&hasher.finalize())
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The current time, or `SOURCE_DATE_EPOCH` if it's set, in UTC.
+fn creation_time() -> String {
+  let secs = std::env::var("SOURCE_DATE_EPOCH")
+    .ok()
+    .and_then(|s| s.trim().parse::<i64>().ok())
+    .unwrap_or_else(|| {
+      std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+    });
+  format_utc(secs)
+}
+
+/// Format a unix timestamp like `2024-01-31T12:00:00Z`.
+fn format_utc(secs: i64) -> String {
+  let days = secs.div_euclid(86400);
+  let rem = secs.rem_euclid(86400);
+  // Howard Hinnant's days_from_civil, run backwards
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    rem / 3600,
+    rem % 3600 / 60,
+    rem % 60
+  )
+}
@@ -0,0 +1,233 @@
+//! Positions in source files, for error messages.
+//!
+//! Parsers go through the source a line at a time with [`lines`], and
+//! every line knows where it is, so nothing has to be searched for
+//! afterwards.
+
+/// A piece of a source file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+  /// Byte offset of the start, from the start of the source.
+  pub offset: usize,
+  /// Length in bytes.
+  pub len: usize,
+  /// Line of the start, counting from 1.
+  pub line: usize,
+  /// Column of the start in characters, counting from 1.
+  pub column: usize,
+}
+
+impl Span {
+  /// From the start of `self` to the end of `other`.
+  pub fn to(self, other: Span) -> Span {
+    Span {
+      len: (other.offset + other.len).saturating_sub(self.offset),
+      ..self
+    }
+  }
+
+  /// The same span, in a bigger source that this one's source is a piece
+  /// of. The piece starts `offset` bytes in, at the start of line `line`.
+  pub(crate) fn shifted(self, offset: usize, line: usize) -> Span {
+    Span {
+      offset: self.offset + offset,
+      line: self.line + line - 1,
+      ..self
+    }
+  }
+
+  /// The text this covers in `source`.
+  pub fn text(self, source: &str) -> &str {
+    source
+      .get(self.offset..self.offset + self.len)
+      .unwrap_or_default()
+  }
+}
+
+/// `line:column`, like editors take.
+impl std::fmt::Display for Span {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}:{}", self.line, self.column)
+  }
+}
+
+/// A line of a source file, or a piece of one, that knows where it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Line<'s> {
+  pub(crate) text: &'s str,
+  pub(crate) offset: usize,
+  /// Counting from 1.
+  pub(crate) number: usize,
+  /// Column of the start of `text`, counting from 1.
+  pub(crate) column: usize,
+}
+
+/// Split `source` into lines on `\n`, like `source.split('\n')`.
+pub(crate) fn lines(source: &str) -> impl Iterator<Item = Line<'_>> + '_ {
+  let mut offset = 0;
+  source.split('\n').enumerate().map(move |(idx, text)| {
+    let line = Line {
+      text,
+      offset,
+      number: idx + 1,
+      column: 1,
+    };
+    offset += text.len() + 1;
+    line
+  })
+}
+
+impl<'s> Line<'s> {
+  /// `text`, which starts where `span` does.
+  pub(crate) fn at(text: &'s str, span: Span) -> Line<'s> {
+    Line {
+      text,
+      offset: span.offset,
+      number: span.line,
+      column: span.column,
+    }
+  }
+
+  /// The span of the whole thing.
+  pub(crate) fn span(&self) -> Span {
+    self.span_at(0, self.text.len())
+  }
+
+  /// The span of `len` bytes, `start` bytes in.
+  pub(crate) fn span_at(&self, start: usize, len: usize) -> Span {
+    Span {
+      offset: self.offset + start,
+      len,
+      line: self.number,
+      column: self.column + self.text[..start].chars().count(),
+    }
+  }
+
+  /// The part of the line from `start` bytes in to `end`.
+  pub(crate) fn slice(&self, start: usize, end: usize) -> Line<'s> {
+    Line {
+      text: &self.text[start..end],
+      offset: self.offset + start,
+      number: self.number,
+      column: self.column + self.text[..start].chars().count(),
+    }
+  }
+
+  /// The line with whitespace taken off both ends.
+  pub(crate) fn trim(&self) -> Line<'s> {
+    let start = self.text.len() - self.text.trim_start().len();
+    let end = self.text.trim_end().len().max(start);
+    self.slice(start, end)
+  }
+
+  /// The whitespace-separated words in the line.
+  pub(crate) fn words(&self) -> impl Iterator<Item = Line<'s>> + '_ {
+    let mut rest = 0;
+    std::iter::from_fn(move || {
+      let tail = &self.text[rest..];
+      let start = rest + (tail.len() - tail.trim_start().len());
+      if start == self.text.len() {
+        return None;
+      }
+      let end = self.text[start..]
+        .find(char::is_whitespace)
+        .map_or(self.text.len(), |idx| start + idx);
+      rest = end;
+      Some(self.slice(start, end))
+    })
+  }
+}
+
+/// An error at a place in a source file.
+///
+/// It displays like rustc's errors, with the line and a caret under the
+/// problem:
+///
+/// ```text
+/// could not find `:` in field header line
+///  --> 5:1
+///   |
+/// 5 | this is not a field
+///   | ^^^^^^^^^^^^^^^^^^^
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedError {
+  pub message: String,
+  pub span: Span,
+  /// The whole line the span starts on.
+  line_text: String,
+  /// How many carets go under it.
+  width: usize,
+}
+
+impl SpannedError {
+  pub fn new(
+    source: &str,
+    span: Span,
+    message: impl std::fmt::Display,
+  ) -> Self {
+    // Only the part on the first line gets carets
+    let first_line = span.text(source).split('\n').next().unwrap_or_default();
+    Self {
+      message: message.to_string(),
+      span,
+      line_text: snippet_line(source, span).to_owned(),
+      width: first_line.trim_end_matches('\r').chars().count().max(1),
+    }
+  }
+
+  /// Like [`Span::shifted`]. The snippet stays the same.
+  pub(crate) fn shifted(mut self, offset: usize, line: usize) -> Self {
+    self.span = self.span.shifted(offset, line);
+    self
+  }
+
+  /// Just the source line and the carets, without the message or position.
+  pub fn snippet(&self) -> String {
+    let number = self.span.line.to_string();
+    let gutter = " ".repeat(number.len());
+    // Tabs stay tabs, so the caret lines up however wide they are
+    let padding: String = self
+      .line_text
+      .chars()
+      .take(self.span.column.saturating_sub(1))
+      .map(|c| if c == '\t' { '\t' } else { ' ' })
+      .collect();
+    format!(
+      "{gutter} |\n{number} | {}\n{gutter} | {padding}{}",
+      &self.line_text,
+      "^".repeat(self.width)
+    )
+  }
+}
+
+/// A [`SpannedError`] as an [`eyre::Error`], for parsers to return.
+#[track_caller]
+pub(crate) fn error(
+  source: &str,
+  span: Span,
+  message: impl std::fmt::Display,
+) -> eyre::Error {
+  eyre::Report::new(SpannedError::new(source, span, message))
+}
+
+/// The line `span` starts on, without its newline.
+fn snippet_line(source: &str, span: Span) -> &str {
+  let offset = span.offset.min(source.len());
+  let start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+  let end = source[offset..]
+    .find('\n')
+    .map_or(source.len(), |idx| offset + idx);
+  source[start..end].trim_end_matches('\r')
+}
+
+impl std::fmt::Display for SpannedError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let gutter = " ".repeat(self.span.line.to_string().len());
+    writeln!(f, "{}", &self.message)?;
+    writeln!(f, "{}--> {}", &gutter, self.span)?;
+    f.write_str(&self.snippet())
+  }
+}
+
+impl std::error::Error for SpannedError {}
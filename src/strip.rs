@@ -1,115 +1,450 @@
 //! Strip the excludes out of an orig tarball.
 
-use std::{io::BufReader, path::PathBuf, str::FromStr};
+use std::{
+  io::{BufReader, Read, Write},
+  path::{Path, PathBuf},
+  str::FromStr,
+};
 
 use clap::Args;
 use eyre::{Context, eyre};
 use indicatif::ProgressBar;
+use log::warn;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 // i do not really like how this crate sets up its exports
-use xz2::{bufread::XzDecoder, write::XzEncoder};
+use xz2::write::XzEncoder;
 
-use crate::deb822::copyright::CopyrightFile;
+use crate::{
+  changelog::{self, Changelog},
+  compression,
+  deb822::copyright::{self, CopyrightFile},
+  filter::{GlobList, PathFilter},
+  glob::Glob,
+  sbom::{SbomFormat, SpdxDocument},
+};
 
 /// Strip `Files-Excluded` from the orig tarball.
 #[derive(Args)]
 pub struct Strip {
-  /// Original tar.xz file.
+  /// Original tarball. Usually a tar.xz, but tar.gz and plain tar
+  /// work too.
   #[arg(short, long)]
   input: PathBuf,
   /// Path to where the stripped tar.xz file should go.
+  /// [default: `<source>_<upstream>.orig.tar.xz` from the newest
+  /// changelog entry, next to the input]
   #[arg(short, long)]
-  output: PathBuf,
+  output: Option<PathBuf>,
   /// Path to the debian copyright file.
   /// [default: ./debian/copyright]
   #[arg(short, long)]
   debfile: Option<PathBuf>,
+  /// Path to the debian changelog. This names the output if there is no
+  /// --output, and dates the SBOM if `SOURCE_DATE_EPOCH` is not set.
+  /// [default: ./debian/changelog]
+  #[arg(long, value_name = "FILE")]
+  changelog: Option<PathBuf>,
+  /// Also exclude paths matching this glob.
+  /// May be given multiple times.
+  #[arg(long, value_name = "GLOB")]
+  exclude: Vec<String>,
+  /// Also exclude paths matching the globs in this file.
+  /// Globs are whitespace-separated, and lines starting with `#` are
+  /// comments. May be given multiple times.
+  #[arg(long, value_name = "FILE")]
+  exclude_from: Vec<PathBuf>,
   /// If this is set, do not actually write the output file.
   #[arg(long)]
   dry_run: bool,
+  /// Also write an SPDX document listing every kept file, with its
+  /// checksums and license, to this path.
+  #[arg(long, value_name = "FILE")]
+  sbom: Option<PathBuf>,
+  /// Format of the SPDX document.
+  /// [default: spdx-json if the --sbom path ends in .json, else spdx]
+  #[arg(long, value_enum, requires = "sbom")]
+  sbom_format: Option<SbomFormat>,
 }
 
 impl Strip {
   pub fn do_it(self) -> eyre::Result<()> {
-    let copyright = {
-      let path = self.debfile.unwrap_or(PathBuf::from("./debian/copyright"));
-      let copyright_file =
-        std::fs::read_to_string(&path).wrap_err_with(|| {
-          eyre!("could not read copyright file at {}", path.display())
-        })?;
+    let copyright = CopyrightFile::from_path(
+      self
+        .debfile
+        .unwrap_or(PathBuf::from(copyright::DEFAULT_PATH)),
+    )?;
 
-      CopyrightFile::from_str(&copyright_file)
-        .wrap_err(eyre!("could not parse copyright file"))?
+    // The changelog is only required to name the output, but if it's
+    // there anyways it also dates the SBOM.
+    let changelog_path = self
+      .changelog
+      .clone()
+      .unwrap_or(PathBuf::from(changelog::DEFAULT_PATH));
+    let changelog_required = self.output.is_none() || self.changelog.is_some();
+    let changelog = if changelog_required {
+      Some(Changelog::from_path(&changelog_path)?)
+    } else if changelog_path.exists() {
+      // Only the SBOM date depends on it now, and that has other
+      // fallbacks, so a broken changelog shouldn't stop the strip.
+      Changelog::from_path(&changelog_path)
+        .inspect_err(|err| {
+          warn!(
+            "ignoring changelog at {}: {:#}",
+            changelog_path.display(),
+            err
+          )
+        })
+        .ok()
+    } else {
+      None
+    };
+    let latest = changelog.as_ref().and_then(Changelog::latest);
+    let output = match (&self.output, latest) {
+      (Some(output), _) => output.clone(),
+      (None, Some(latest)) => {
+        self.input.with_file_name(latest.orig_tarball_name("xz"))
+      }
+      (None, None) => {
+        return Err(eyre!(
+          "no --output given, and changelog at {} has no entries",
+          changelog_path.display()
+        ));
+      }
     };
 
+    // Compiling the list is the slow part, so it's only done once
+    let mut extra_globs = Vec::new();
+    for glob_str in &self.exclude {
+      let glob = Glob::from_str(glob_str).wrap_err_with(|| {
+        eyre!("while parsing --exclude glob {:?}", glob_str)
+      })?;
+      extra_globs.push(glob);
+    }
+    for path in &self.exclude_from {
+      let globs = std::fs::read_to_string(path)
+        .map_err(eyre::Error::from)
+        .and_then(|s| GlobList::from_str(&s))
+        .wrap_err_with(|| {
+          eyre!("could not read exclude file at {}", path.display())
+        })?;
+      extra_globs.extend(globs.into_globs());
+    }
+    let extra_excludes = GlobList::new(extra_globs);
+
     let in_file = std::fs::File::options()
       .read(true)
       .open(&self.input)
       .wrap_err_with(|| {
         eyre!("could not open input file at {}", self.input.display())
       })?;
-    let xz = XzDecoder::new(BufReader::new(in_file));
-    let mut xz_tar_reader = tar::Archive::new(xz);
 
-    let mut tar_xz_writer = if self.dry_run {
-      None
+    // I can't find a good way to see how much of the tar file I have read.
+    let progress_spinner = ProgressBar::new_spinner();
+    let mut seen_count = 0usize;
+    let spinner = progress_spinner.clone();
+    let mut stripper = Stripper::new((&copyright).or(extra_excludes))
+      .checksums(self.sbom.is_some())
+      .on_entry(move |entry, decision| {
+        seen_count += 1;
+        // Only print every so often because you can't read that fast anyways
+        if seen_count.is_multiple_of(10) {
+          spinner.set_message(format!(
+            "{} {}",
+            match decision {
+              Decision::Keep => "incl",
+              Decision::Exclude => "excl",
+            },
+            entry.path.display()
+          ));
+        }
+        decision
+      });
+
+    let report = if self.dry_run {
+      stripper.dry_run(in_file)?
     } else {
       let out_file = std::fs::File::options()
         .create(true)
         .write(true)
         .truncate(true)
-        .open(&self.output)
+        .open(&output)
         .wrap_err_with(|| {
-          eyre!("could not open output file at {}", self.output.display())
+          eyre!("could not open output file at {}", output.display())
         })?;
-      // TODO is it yak shaving to allow custom compression amount
-      let xz = XzEncoder::new(out_file, 6);
-      Some(tar::Builder::new(xz))
+      stripper.strip(in_file, out_file)?
     };
 
+    progress_spinner.finish_with_message(format!(
+      "kept {}/{} entries from the archive",
+      report.keep_count, report.total_count
+    ));
+
+    if let Some(sbom_path) = &self.sbom {
+      let format = self.sbom_format.unwrap_or_else(|| {
+        SbomFormat::from_file_name(&sbom_path.to_string_lossy())
+      });
+      let name = output
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+      let name = name.split(".orig.tar").next().unwrap_or_default();
+      let sbom_file = std::fs::File::create(sbom_path).wrap_err_with(|| {
+        eyre!("could not open SBOM file at {}", sbom_path.display())
+      })?;
+      let mut document = SpdxDocument::new(name, &copyright, &report.files);
+      if let Some(latest) = latest {
+        document = document.with_fallback_time(latest.date.timestamp());
+      }
+      document
+        .write(format, std::io::BufWriter::new(sbom_file))
+        .wrap_err("could not write SBOM")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// What should happen to one entry of the input tarball.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+  Keep,
+  Exclude,
+}
+
+/// Information about one entry of the input tarball, handed to the
+/// [`PathFilter`] and the [`Stripper::on_entry`] callback.
+pub struct EntryInfo<'e> {
+  /// The path that gets checked against the copyright file.
+  /// This is the path in the archive with the first component removed,
+  /// because tarfile paths for `foo-bar.tar.xz` start with `foo-bar/`.
+  pub path: &'e Path,
+  /// The path exactly as it appears in the archive.
+  pub archive_path: &'e Path,
+  /// The tar header of the entry.
+  pub header: &'e tar::Header,
+  /// The size of the entry's data in bytes.
+  pub size: u64,
+}
+
+/// Statistics about a finished strip.
+#[derive(Debug, Clone, Default)]
+pub struct StripReport {
+  /// How many entries were in the input archive.
+  pub total_count: usize,
+  /// How many of those entries were kept.
+  pub keep_count: usize,
+  /// The archive paths of every excluded entry, in archive order.
+  pub excluded: Vec<PathBuf>,
+  /// Every kept regular file, in archive order.
+  /// This is only filled in if [`Stripper::checksums`] is on.
+  pub files: Vec<KeptFile>,
+}
+
+/// A regular file that made it into the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeptFile {
+  /// The path with the leading `foo-bar/` taken off, like
+  /// [`EntryInfo::path`].
+  pub path: PathBuf,
+  pub sha1: [u8; 20],
+  pub sha256: [u8; 32],
+}
+
+type EntryCallback<'a> =
+  Box<dyn FnMut(&EntryInfo<'_>, Decision) -> Decision + 'a>;
+
+/// Library-level version of the `strip` subcommand.
+///
+/// This reads a `.tar.xz` (or `.tar.gz`, or plain `.tar`) from any
+/// [`Read`] and writes the `.tar.xz` with the excluded files taken out to
+/// any [`Write`], so it does not care whether those are files on disk or
+/// buffers in memory.
+///
+/// Which files are excluded is up to the [`PathFilter`]. Usually this is
+/// a [`CopyrightFile`], maybe combined with some other rules.
+pub struct Stripper<'a, F> {
+  filter: F,
+  compression_level: u32,
+  checksums: bool,
+  on_entry: Option<EntryCallback<'a>>,
+}
+
+impl<'a, F: PathFilter> Stripper<'a, F> {
+  pub fn new(filter: F) -> Self {
+    Self {
+      filter,
+      compression_level: 6,
+      checksums: false,
+      on_entry: None,
+    }
+  }
+
+  /// Set the xz compression level of the output, from 0 to 9.
+  /// The default is 6, same as the `xz` command line tool.
+  pub fn compression_level(mut self, level: u32) -> Self {
+    self.compression_level = level;
+    self
+  }
+
+  /// Checksum every kept file, and list them in [`StripReport::files`].
+  /// This is off by default, because it means reading all the data even
+  /// on a dry run.
+  pub fn checksums(mut self, checksums: bool) -> Self {
+    self.checksums = checksums;
+    self
+  }
+
+  /// Call `f` for every entry of the input archive.
+  ///
+  /// It gets the decision the filter made about the entry,
+  /// and whatever it returns is what actually happens to the entry.
+  /// Just return the decision unchanged to only observe the strip.
+  pub fn on_entry<C>(mut self, f: C) -> Self
+  where
+    C: FnMut(&EntryInfo<'_>, Decision) -> Decision + 'a,
+  {
+    self.on_entry = Some(Box::new(f));
+    self
+  }
+
+  /// Read a `.tar.xz` from `input`, and write it minus the excluded entries
+  /// to `output`.
+  pub fn strip<R: Read, W: Write>(
+    &mut self,
+    input: R,
+    output: W,
+  ) -> eyre::Result<StripReport> {
+    let xz = XzEncoder::new(output, self.compression_level);
+    let (report, tar_xz_writer) =
+      self.run(input, Some(tar::Builder::new(xz)))?;
+    if let Some(txzw) = tar_xz_writer {
+      txzw
+        .into_inner()
+        .and_then(|xz| xz.finish())
+        .wrap_err("could not finish writing output tar file")?;
+    }
+    Ok(report)
+  }
+
+  /// Go through the whole input archive like [`Stripper::strip`],
+  /// but don't write anything anywhere.
+  pub fn dry_run<R: Read>(&mut self, input: R) -> eyre::Result<StripReport> {
+    let (report, _) = self.run::<R, XzEncoder<std::io::Sink>>(input, None)?;
+    Ok(report)
+  }
+
+  fn run<R: Read, W: Write>(
+    &mut self,
+    input: R,
+    mut tar_xz_writer: Option<tar::Builder<W>>,
+  ) -> eyre::Result<(StripReport, Option<tar::Builder<W>>)> {
+    let decoder = compression::decompress(BufReader::new(input))
+      .wrap_err("could not read input tar file")?;
+    let mut xz_tar_reader = tar::Archive::new(decoder);
+
     // this is hard to write as an iterator train because of propogating errors
-    let mut keep_count = 0;
-    let mut total_count = 0;
-    // I can't find a good way to see how much of the tar file I have read.
-    let progress_spinner = ProgressBar::new_spinner();
+    let mut report = StripReport::default();
     for entry in xz_tar_reader
       .entries()
       .wrap_err("could not read entries from input tarfile")?
     {
       let mut entry = entry.wrap_err("malformed entry in input tar file")?;
 
-      let real_path = entry.path()?.into_owned();
+      // The path bytes go straight through, so names that aren't UTF-8
+      // are matched and written out exactly as they are.
+      let real_path = path_from_bytes(&entry.path_bytes());
       // tarfile paths for `foo-bar.tar.xz` start with `foo-bar/`
       // so skip that
       let checked_path: PathBuf = real_path.components().skip(1).collect();
-      let exclude = copyright.is_path_excluded(&checked_path);
-      if !exclude {
-        keep_count += 1;
-        if let Some(ref mut txzw) = tar_xz_writer {
-          let mut header = entry.header().clone();
-          txzw.append_data(&mut header, &real_path, &mut entry)?;
-        }
+      let info = EntryInfo {
+        path: &checked_path,
+        archive_path: &real_path,
+        header: entry.header(),
+        size: entry.size(),
+      };
+      let mut decision = if self.filter.is_excluded(&info) {
+        Decision::Exclude
+      } else {
+        Decision::Keep
+      };
+      if let Some(ref mut on_entry) = self.on_entry {
+        decision = on_entry(&info, decision);
       }
 
-      total_count += 1;
-      // Only print every so often because you can't read that fast anyways
-      if total_count % 10 == 0 {
-        progress_spinner.set_message(format!(
-          "{} {}",
-          if exclude { "excl" } else { "incl" },
-          checked_path.display()
-        ));
+      match decision {
+        Decision::Keep => {
+          report.keep_count += 1;
+          let hash = self.checksums && entry.header().entry_type().is_file();
+          let mut header = entry.header().clone();
+          // Hashing is slow on big tarballs, so only do it if asked to
+          if hash {
+            let mut reader = HashingReader::new(&mut entry);
+            if let Some(ref mut txzw) = tar_xz_writer {
+              txzw.append_data(&mut header, &real_path, &mut reader)?;
+            } else {
+              std::io::copy(&mut reader, &mut std::io::sink())
+                .wrap_err("could not read entry from input tar file")?;
+            }
+            let (sha1, sha256) = reader.finish();
+            report.files.push(KeptFile {
+              path: checked_path,
+              sha1,
+              sha256,
+            });
+          } else if let Some(ref mut txzw) = tar_xz_writer {
+            txzw.append_data(&mut header, &real_path, &mut entry)?;
+          }
+        }
+        Decision::Exclude => report.excluded.push(real_path),
       }
+      report.total_count += 1;
     }
 
-    if let Some(mut txzw) = tar_xz_writer {
-      txzw.finish()?;
+    Ok((report, tar_xz_writer))
+  }
+}
+
+/// Turn the raw bytes of a tar entry's path into a [`PathBuf`], without
+/// going through UTF-8.
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+  use std::os::unix::ffi::OsStrExt;
+  PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+/// Other platforms can't hold arbitrary bytes in a path, so this is lossy.
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+  PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Passes reads through, hashing everything that goes by.
+struct HashingReader<R> {
+  inner: R,
+  sha1: Sha1,
+  sha256: Sha256,
+}
+
+impl<R> HashingReader<R> {
+  fn new(inner: R) -> Self {
+    Self {
+      inner,
+      sha1: Sha1::new(),
+      sha256: Sha256::new(),
     }
+  }
 
-    progress_spinner.finish_with_message(format!(
-      "kept {}/{} entries from the archive",
-      keep_count, total_count
-    ));
+  fn finish(self) -> ([u8; 20], [u8; 32]) {
+    (self.sha1.finalize().into(), self.sha256.finalize().into())
+  }
+}
 
-    Ok(())
+impl<R: Read> Read for HashingReader<R> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let n = self.inner.read(buf)?;
+    self.sha1.update(&buf[..n]);
+    self.sha256.update(&buf[..n]);
+    Ok(n)
   }
 }
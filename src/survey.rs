@@ -0,0 +1,237 @@
+//! Find every source package in a local Debian mirror whose copyright
+//! file has a `Files-Excluded` field.
+//!
+//! This reads the `Sources` indices under `dists/`, then opens each
+//! package's debian tarball from `pool/` to get at `debian/copyright`.
+//! Only the copyright file is read out of each tarball.
+
+use std::{
+  io::Read,
+  path::{Component, Path, PathBuf},
+  str::FromStr,
+};
+
+use clap::Args;
+use eyre::{Context, eyre};
+use log::{debug, info, warn};
+
+use crate::{
+  compression,
+  deb822::{
+    DuplicateFields, FieldKind, Stanza, checksums::Checksum,
+    copyright::CopyrightFile, stream::StanzaReader,
+  },
+  glob::Glob,
+};
+
+/// Compression suffixes for `Sources` indices, in order of preference.
+const INDEX_SUFFIXES: &[&str] = &[".xz", ".gz", ""];
+
+/// List the source packages in a mirror that use `Files-Excluded`.
+///
+/// Prints one line per package: the name, the version, and the globs,
+/// separated by tabs. Packages that can't be read are skipped with
+/// a warning.
+#[derive(Args)]
+pub struct Survey {
+  /// Root of the mirror, the directory with `dists` and `pool` in it.
+  #[arg(short, long)]
+  mirror: PathBuf,
+  /// Which distribution's indices to read.
+  #[arg(short, long, default_value = "unstable")]
+  dist: String,
+}
+
+impl Survey {
+  pub fn do_it(self) -> eyre::Result<()> {
+    let indices = source_indices(&self.mirror, &self.dist)?;
+    let mut total = 0;
+    let mut hits = 0;
+    for index in &indices {
+      info!("reading {}", index.display());
+      let reader = StanzaReader::open(index, DuplicateFields::Keep)?;
+      for stanza in reader {
+        let stanza = match stanza {
+          Ok(stanza) => stanza,
+          Err(err) => {
+            warn!("in {}: {:#}", index.display(), err);
+            continue;
+          }
+        };
+        total += 1;
+        match survey_package(&self.mirror, &stanza) {
+          Ok(Some(hit)) => {
+            hits += 1;
+            println!("{}", hit);
+          }
+          Ok(None) => {}
+          Err(err) => warn!("{:#}", err),
+        }
+      }
+    }
+    eprintln!("{} of {} source packages have Files-Excluded", hits, total);
+    Ok(())
+  }
+}
+
+/// A package whose copyright file has `Files-Excluded` in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurveyHit {
+  pub package: String,
+  pub version: String,
+  pub excludes: Vec<Glob>,
+}
+
+impl std::fmt::Display for SurveyHit {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}\t{}\t", &self.package, &self.version)?;
+    for (idx, glob) in self.excludes.iter().enumerate() {
+      if idx > 0 {
+        f.write_str(" ")?;
+      }
+      write!(f, "{}", glob)?;
+    }
+    Ok(())
+  }
+}
+
+/// Find the `Sources` index of every component of `dist` in the mirror,
+/// like `dists/unstable/main/source/Sources.xz`.
+///
+/// If a component has its index in several compressions, only one of
+/// them is used.
+pub fn source_indices(mirror: &Path, dist: &str) -> eyre::Result<Vec<PathBuf>> {
+  let dist_dir = mirror.join("dists").join(dist);
+  let entries = std::fs::read_dir(&dist_dir)
+    .wrap_err_with(|| eyre!("could not read {}", dist_dir.display()))?;
+  let mut indices = Vec::new();
+  for entry in entries {
+    let source_dir = entry?.path().join("source");
+    let index = INDEX_SUFFIXES
+      .iter()
+      .map(|suffix| source_dir.join(format!("Sources{}", suffix)))
+      .find(|path| path.is_file());
+    if let Some(index) = index {
+      indices.push(index);
+    }
+  }
+  if indices.is_empty() {
+    return Err(eyre!("no Sources indices found in {}", dist_dir.display()));
+  }
+  indices.sort();
+  Ok(indices)
+}
+
+/// Look at the copyright file of the package described by `stanza`, an
+/// entry of a `Sources` index.
+///
+/// Returns `None` if the package doesn't use `Files-Excluded`, if it has
+/// no debian tarball (like the 1.0 format with a `.diff.gz`), or if its
+/// copyright file isn't in the machine-readable format.
+pub fn survey_package(
+  mirror: &Path,
+  stanza: &Stanza,
+) -> eyre::Result<Option<SurveyHit>> {
+  let field = |name: &str| {
+    stanza
+      .get(name)
+      .map(|field| field.value(FieldKind::Simple))
+      .ok_or_else(|| eyre!("source package has no {} field", name))
+  };
+  let package = field("Package")?;
+  let version = field("Version")?;
+  let directory = field("Directory")?;
+
+  let files = match stanza.get("Checksums-Sha256") {
+    Some(checksums) => Checksum::parse_list(Some(checksums)),
+    None => Checksum::parse_list(stanza.get("Files")),
+  }
+  .wrap_err_with(|| eyre!("in source package {} {}", &package, &version))?;
+  let Some(tarball) = debian_tarball(&files) else {
+    debug!("{} {} has no debian tarball, skipping", &package, &version);
+    return Ok(None);
+  };
+
+  let path = mirror.join(&directory).join(tarball);
+  let Some(text) =
+    read_copyright(&path).wrap_err_with(|| eyre!("in {}", path.display()))?
+  else {
+    debug!("{} has no debian/copyright", path.display());
+    return Ok(None);
+  };
+
+  let copyright = match CopyrightFile::from_str(&text) {
+    Ok(copyright) => copyright,
+    // Plenty of packages still have free-form copyright files, which
+    // don't parse. Only complain if it looks like it mattered.
+    Err(err) if text.to_ascii_lowercase().contains("files-excluded") => {
+      return Err(err.wrap_err(eyre!(
+        "could not parse the copyright file of {} {}",
+        &package,
+        &version
+      )));
+    }
+    Err(err) => {
+      debug!("{} {}: {:#}", &package, &version, err);
+      return Ok(None);
+    }
+  };
+  if copyright.excludes().is_empty() {
+    return Ok(None);
+  }
+  Ok(Some(SurveyHit {
+    package,
+    version,
+    excludes: copyright.excludes().to_vec(),
+  }))
+}
+
+/// Pick the tarball with the `debian` directory in it: the
+/// `.debian.tar.*` one, or for native packages, the only tarball.
+fn debian_tarball(files: &[Checksum]) -> Option<&str> {
+  let is_tarball =
+    |name: &str| name.contains(".tar.") || name.ends_with(".tar");
+  files
+    .iter()
+    .map(|file| file.name.as_str())
+    .find(|name| name.contains(".debian.tar"))
+    .or_else(|| {
+      let mut tarballs = files
+        .iter()
+        .map(|file| file.name.as_str())
+        .filter(|name| is_tarball(name) && !name.contains(".orig"));
+      match (tarballs.next(), tarballs.next()) {
+        (Some(name), None) => Some(name),
+        _ => None,
+      }
+    })
+}
+
+/// Read `debian/copyright` out of the tarball at `path`. It can be at the
+/// top level, or inside one directory, as in native tarballs.
+fn read_copyright(path: &Path) -> eyre::Result<Option<String>> {
+  let mut archive = tar::Archive::new(compression::open(path)?);
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let entry_path = entry.path()?;
+    let components: Vec<Component> = entry_path
+      .components()
+      .filter(|component| *component != Component::CurDir)
+      .collect();
+    let tail = &components[components.len().saturating_sub(2)..];
+    if components.len() <= 3
+      && tail
+        == [
+          Component::Normal("debian".as_ref()),
+          Component::Normal("copyright".as_ref()),
+        ]
+    {
+      let mut text = String::new();
+      entry
+        .read_to_string(&mut text)
+        .wrap_err("could not read debian/copyright")?;
+      return Ok(Some(text));
+    }
+  }
+  Ok(None)
+}
@@ -0,0 +1,260 @@
+//! Debian package versions, like `1:2.30+dfsg-1`.
+//!
+//! The syntax and ordering are in the `Version` section of Debian policy:
+//! https://www.debian.org/doc/debian-policy/ch-controlfields#version
+//!
+//! The ordering is the same as `dpkg --compare-versions`, so versions
+//! that are written differently can still be equal, like `1.0` and
+//! `1.0-0`, or `1.09` and `1.9`.
+
+use std::{cmp::Ordering, str::FromStr};
+
+use eyre::{bail, eyre};
+
+/// A Debian version: `[epoch:]upstream_version[-debian_revision]`.
+#[derive(Debug, Clone)]
+pub struct Version {
+  /// Zero if not given.
+  pub epoch: u32,
+  pub upstream: String,
+  /// The part after the last `-`, if there is one. Native packages
+  /// don't have one.
+  pub revision: Option<String>,
+}
+
+/// Repack suffixes that mark an orig tarball as having had
+/// `Files-Excluded` stripped out.
+pub const REPACK_SUFFIXES: &[&str] = &["+dfsg", "+ds"];
+
+impl Version {
+  /// Find the repack suffix, like `+dfsg1` or `+ds.2`, in the upstream
+  /// version. Everything from the suffix to the end counts.
+  pub fn repack_suffix(&self) -> Option<&str> {
+    self.repack_suffix_start().map(|idx| &self.upstream[idx..])
+  }
+
+  fn repack_suffix_start(&self) -> Option<usize> {
+    REPACK_SUFFIXES
+      .iter()
+      .filter_map(|suffix| {
+        self.upstream.rmatch_indices(suffix).find(|(idx, _)| {
+          // `+dsp` is not a repack suffix, but `+ds1` is
+          !self.upstream[idx + suffix.len()..]
+            .starts_with(|c: char| c.is_ascii_alphabetic())
+        })
+      })
+      .map(|(idx, _)| idx)
+      .max()
+  }
+
+  /// This version with the repack suffix taken off.
+  pub fn without_repack_suffix(&self) -> Version {
+    let mut out = self.clone();
+    if let Some(idx) = self.repack_suffix_start() {
+      out.upstream.truncate(idx);
+    }
+    out
+  }
+
+  /// This version with `suffix` (like `+dfsg`) as its repack suffix,
+  /// replacing any repack suffix it already had.
+  pub fn with_repack_suffix(&self, suffix: &str) -> Version {
+    let mut out = self.without_repack_suffix();
+    out.upstream.push_str(suffix);
+    out
+  }
+
+  /// The name of the orig tarball for this version of `package`, like
+  /// `foo_1.0+dfsg.orig.tar.xz` for `ext` `xz`.
+  ///
+  /// The epoch and revision are not part of the name.
+  pub fn orig_tarball_name(&self, package: &str, ext: &str) -> String {
+    format!("{}_{}.orig.tar.{}", package, &self.upstream, ext)
+  }
+}
+
+impl FromStr for Version {
+  type Err = eyre::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    parse(s.trim()).map_err(|err| err.wrap_err(eyre!("in version {:?}", s)))
+  }
+}
+
+fn parse(s: &str) -> eyre::Result<Version> {
+  if s.is_empty() {
+    bail!("version is empty");
+  }
+  if s.contains(char::is_whitespace) {
+    bail!("version has whitespace in it");
+  }
+
+  let (epoch, rest) = match s.split_once(':') {
+    Some((epoch, rest)) => {
+      if epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()) {
+        bail!("epoch {:?} is not a number", epoch);
+      }
+      let epoch = epoch
+        .parse()
+        .map_err(|_| eyre!("epoch {:?} is too big", epoch))?;
+      (epoch, rest)
+    }
+    None => (0, s),
+  };
+  let (upstream, revision) = match rest.rsplit_once('-') {
+    Some((upstream, revision)) => {
+      if revision.is_empty() {
+        bail!("revision is empty");
+      }
+      (upstream, Some(revision))
+    }
+    None => (rest, None),
+  };
+
+  if upstream.is_empty() {
+    bail!("upstream version is empty");
+  }
+  if !upstream.starts_with(|c: char| c.is_ascii_digit()) {
+    bail!("upstream version must start with a digit");
+  }
+  if let Some(c) = upstream
+    .chars()
+    .find(|&c| !(c.is_ascii_alphanumeric() || ".+-~".contains(c)))
+  {
+    bail!("invalid character {:?} in upstream version", c);
+  }
+  if let Some(c) = revision.and_then(|revision| {
+    revision
+      .chars()
+      .find(|&c| !(c.is_ascii_alphanumeric() || ".+~".contains(c)))
+  }) {
+    bail!("invalid character {:?} in revision", c);
+  }
+
+  Ok(Version {
+    epoch,
+    upstream: upstream.to_owned(),
+    revision: revision.map(str::to_owned),
+  })
+}
+
+// Comparison.
+// This is a port of `verrevcmp` from dpkg's lib/dpkg/version.c.
+
+/// Sort weight of a character in a non-digit part. `~` sorts before
+/// everything, even the end of the part, and letters sort before
+/// everything else.
+fn order(c: Option<u8>) -> i32 {
+  match c {
+    None => 0,
+    Some(c) if c.is_ascii_digit() => 0,
+    Some(c) if c.is_ascii_alphabetic() => c as i32,
+    Some(b'~') => -1,
+    Some(c) => c as i32 + 256,
+  }
+}
+
+fn verrevcmp(a: &str, b: &str) -> Ordering {
+  let (a, b) = (a.as_bytes(), b.as_bytes());
+  let (mut i, mut j) = (0, 0);
+  let is_digit =
+    |s: &[u8], idx: usize| s.get(idx).is_some_and(u8::is_ascii_digit);
+
+  while i < a.len() || j < b.len() {
+    // Non-digit prefix
+    while (i < a.len() && !is_digit(a, i)) || (j < b.len() && !is_digit(b, j)) {
+      let ac = order(a.get(i).copied());
+      let bc = order(b.get(j).copied());
+      if ac != bc {
+        return ac.cmp(&bc);
+      }
+      i += 1;
+      j += 1;
+    }
+
+    // Digit part, compared as numbers of any length
+    while a.get(i) == Some(&b'0') {
+      i += 1;
+    }
+    while b.get(j) == Some(&b'0') {
+      j += 1;
+    }
+    let mut first_diff = Ordering::Equal;
+    while is_digit(a, i) && is_digit(b, j) {
+      if first_diff == Ordering::Equal {
+        first_diff = a[i].cmp(&b[j]);
+      }
+      i += 1;
+      j += 1;
+    }
+    if is_digit(a, i) {
+      return Ordering::Greater;
+    }
+    if is_digit(b, j) {
+      return Ordering::Less;
+    }
+    if first_diff != Ordering::Equal {
+      return first_diff;
+    }
+  }
+  Ordering::Equal
+}
+
+impl Ord for Version {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .epoch
+      .cmp(&other.epoch)
+      .then_with(|| verrevcmp(&self.upstream, &other.upstream))
+      .then_with(|| {
+        verrevcmp(
+          self.revision.as_deref().unwrap_or(""),
+          other.revision.as_deref().unwrap_or(""),
+        )
+      })
+  }
+}
+
+impl PartialOrd for Version {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// Equality is by [`Ord`], so `1.0` equals `1.0-0`.
+impl PartialEq for Version {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Version {}
+
+/// Writes the epoch only if it's not zero.
+impl std::fmt::Display for Version {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.epoch != 0 {
+      write!(f, "{}:", self.epoch)?;
+    }
+    f.write_str(&self.upstream)?;
+    if let Some(revision) = &self.revision {
+      write!(f, "-{}", revision)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Version {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(self)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Version {
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    let s = String::deserialize(d)?;
+    Version::from_str(&s).map_err(serde::de::Error::custom)
+  }
+}
@@ -0,0 +1,160 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::{
+  changelog::{Changelog, ChangelogDate},
+  version::Version,
+};
+
+const CHANGELOG: &str = "\
+hello (2.10+dfsg-3) unstable; urgency=medium, binary-only=yes
+
+  * Raise debhelper compat level to 13.
+
+  * Drop the old patches.
+
+ -- Santiago Vila <sanvila@debian.org>  Sun, 04 Jun 2023 12:00:00 +0200
+
+hello (2.10-2) unstable experimental; urgency=low
+
+  * Initial release.
+
+ -- Santiago Vila <sanvila@debian.org>  Thu, 01 Jan 1970 00:00:00 +0000
+
+Old Changelog:
+anything goes here
+";
+
+#[test]
+fn entries() -> eyre::Result<()> {
+  let changelog = Changelog::from_str(CHANGELOG)?;
+  assert_eq!(changelog.entries.len(), 2);
+
+  let latest = changelog.latest().unwrap();
+  assert_eq!(latest.package, "hello");
+  assert_eq!(latest.version, Version::from_str("2.10+dfsg-3")?);
+  assert_eq!(latest.distributions, vec!["unstable"]);
+  assert_eq!(latest.urgency.as_deref(), Some("medium"));
+  assert_eq!(
+    latest.extra,
+    vec![("binary-only".to_owned(), "yes".to_owned())]
+  );
+  assert_eq!(
+    latest.changes,
+    vec![
+      "  * Raise debhelper compat level to 13.",
+      "",
+      "  * Drop the old patches.",
+    ]
+  );
+  assert_eq!(latest.maintainer, "Santiago Vila <sanvila@debian.org>");
+  assert_eq!(latest.date.timestamp(), 1685872800);
+  assert_eq!(
+    latest.orig_tarball_name("xz"),
+    "hello_2.10+dfsg.orig.tar.xz"
+  );
+
+  let oldest = &changelog.entries[1];
+  assert_eq!(oldest.distributions, vec!["unstable", "experimental"]);
+  assert_eq!(oldest.date.timestamp(), 0);
+
+  // Writing it back out reads the same
+  assert_eq!(Changelog::from_str(&changelog.to_string())?, changelog);
+
+  Ok(())
+}
+
+#[test]
+fn fixture() -> eyre::Result<()> {
+  let changelog =
+    Changelog::from_path("tests/libfakeproject/debian/changelog")?;
+  let latest = changelog.latest().unwrap();
+  assert_eq!(latest.package, "libfakeproject");
+  assert_eq!(latest.version.to_string(), "0.1.0");
+  assert_eq!(latest.date.to_string(), "Mon, 06 Oct 2025 12:11:39 -0500");
+
+  Ok(())
+}
+
+#[test]
+fn dates() -> eyre::Result<()> {
+  let date = ChangelogDate::from_str("29 Feb 2024 23:59:60 -0130")?;
+  assert_eq!(date.weekday(), "Thu");
+  assert_eq!(date.offset, -90);
+  assert_eq!(date.to_string(), "Thu, 29 Feb 2024 23:59:60 -0130");
+  assert!(ChangelogDate::from_str("29 Feb 2000 00:00:00 +0000").is_ok());
+  assert!(ChangelogDate::from_str("30 Apr 2025 00:00:00 +0000").is_ok());
+
+  for bad in [
+    "Mon, 06 Oct 2025",
+    "Mon, 06 Okt 2025 12:11:39 -0500",
+    "Mon, 06 Oct 2025 12:11 -0500",
+    "Mon, 06 Oct 2025 12:11:39 EST",
+    "Mon, 32 Oct 2025 12:11:39 -0500",
+    "Mon, 30 Feb 2024 12:11:39 -0500",
+    "Sun, 29 Feb 2025 12:11:39 -0500",
+    "Thu, 29 Feb 1900 12:11:39 -0500",
+    "Thu, 31 Apr 2025 12:11:39 -0500",
+  ] {
+    assert!(ChangelogDate::from_str(bad).is_err(), "{:?} parsed", bad);
+  }
+
+  Ok(())
+}
+
+/// Errors point at the line they're about.
+/// Change lines that happen to start with `--` aren't the trailer.
+#[test]
+fn dashes_in_changes() -> eyre::Result<()> {
+  let changelog = Changelog::from_str(
+    "\
+foo (1.0-1) unstable; urgency=medium
+
+  * Build with
+ --enable-foo
+
+ -- Jane Doe <jane@example.org>  Mon, 06 Oct 2025 12:11:39 -0500
+",
+  )?;
+  let latest = changelog.latest().unwrap();
+  assert_eq!(latest.changes, vec!["  * Build with", " --enable-foo"]);
+  assert_eq!(latest.maintainer, "Jane Doe <jane@example.org>");
+  Ok(())
+}
+
+#[test]
+fn errors() {
+  let err = Changelog::from_str(
+    "\
+foo (1.0) unstable; urgency=low
+
+  * Change.
+not indented
+",
+  )
+  .unwrap_err();
+  assert!(format!("{:#}", err).contains(" --> 4:1"), "{:#}", err);
+
+  let err = Changelog::from_str(
+    "\
+foo (1.0) unstable; urgency=low
+
+  * Change.
+
+ -- Someone <a@example.com>  Mon, 06 Oct 2025 12:11:39 -0500
+
+bar (bad version) unstable; urgency=low
+",
+  )
+  .unwrap_err();
+  assert!(format!("{:#}", err).contains(" --> 7:6"), "{:#}", err);
+
+  for bad in [
+    "foo 1.0 unstable; urgency=low\n",
+    "foo (1.0) unstable urgency=low\n",
+    "foo (1.0); urgency=low\n",
+    "foo (1.0) unstable; urgency=low\n\n  * Change.\n",
+    "foo (1.0) unstable; urgency=low\n\n -- nobody  Mon, 06 Oct 2025 12:11:39 -0500\n",
+  ] {
+    assert!(Changelog::from_str(bad).is_err(), "{:?} parsed", bad);
+  }
+}
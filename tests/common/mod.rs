@@ -0,0 +1,29 @@
+//! Helpers shared between the integration tests.
+
+use xz2::write::XzEncoder;
+
+/// Build an in-memory `.tar.xz` where every path lives under `proj-1.0/`.
+/// Paths ending in `/` are directories, everything else is a file
+/// containing `contents of {path}`.
+pub fn make_tar_xz(paths: &[&str]) -> eyre::Result<Vec<u8>> {
+  let mut builder = tar::Builder::new(XzEncoder::new(Vec::new(), 1));
+  for path in paths {
+    let mut header = tar::Header::new_gnu();
+    let data = if path.ends_with('/') {
+      header.set_entry_type(tar::EntryType::Directory);
+      header.set_mode(0o755);
+      String::new()
+    } else {
+      header.set_mode(0o644);
+      format!("contents of {}", path)
+    };
+    header.set_size(data.len() as u64);
+    header.set_cksum();
+    builder.append_data(
+      &mut header,
+      format!("proj-1.0/{}", path),
+      data.as_bytes(),
+    )?;
+  }
+  Ok(builder.into_inner()?.finish()?)
+}
@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::deb822::{
+  autopkgtest::TestsControlFile,
+  changes::ChangesFile,
+  checksums::{ChangesFileEntry, Checksum},
+  control::ControlFile,
+  dsc::DscFile,
+};
+
+const CONTROL: &str = "\
+Source: rustc
+Section: devel
+Priority: optional
+Maintainer: Debian Rust Maintainers <pkg-rust-maintainers@alioth-lists.debian.net>
+Uploaders:
+ Ximin Luo <infinity0@debian.org>,
+ Sylvestre Ledru <sylvestre@debian.org>,
+Build-Depends:
+ debhelper-compat (= 13),
+ dpkg-dev (>= 1.17.14),
+ cargo:native (>= 0.60.0) <!pkg.rustc.dlstage0>,
+ llvm-17-dev:native [!armel],
+Standards-Version: 4.6.2
+Homepage: http://www.rust-lang.org/
+Vcs-Git: https://salsa.debian.org/rust-team/rust.git
+X-Custom: kept
+
+Package: rustc
+Architecture: any
+Multi-Arch: allowed
+Pre-Depends: ${misc:Pre-Depends}
+Depends: ${shlibs:Depends}, ${misc:Depends},
+ libstd-rust-dev (= ${binary:Version}), gcc | clang
+Description: Rust systems programming language
+ Rust is a curly-brace, block-structured expression language.
+
+Package: rust-doc
+Architecture: all
+Section: doc
+Description: Rust systems programming language - Documentation
+";
+
+#[test]
+fn control_file() -> eyre::Result<()> {
+  let control = ControlFile::from_str(CONTROL)?;
+
+  assert_eq!(control.source.source, "rustc");
+  assert_eq!(control.source.uploaders.len(), 2);
+  assert_eq!(
+    control
+      .source
+      .build_depends
+      .iter()
+      .map(|entry| entry.to_string())
+      .collect::<Vec<_>>(),
+    vec![
+      "debhelper-compat (= 13)",
+      "dpkg-dev (>= 1.17.14)",
+      "cargo:native (>= 0.60.0) <!pkg.rustc.dlstage0>",
+      "llvm-17-dev:native [!armel]",
+    ]
+  );
+  assert_eq!(
+    control
+      .source
+      .extra
+      .get("X-Custom")
+      .unwrap()
+      .same_line_value
+      .as_deref(),
+    Some("kept")
+  );
+
+  let rustc = control.binary("rustc").unwrap();
+  assert_eq!(rustc.architecture, vec!["any"]);
+  assert_eq!(rustc.depends.0[3].to_string(), "gcc | clang");
+  assert!(
+    rustc
+      .description
+      .as_deref()
+      .unwrap()
+      .starts_with("Rust systems programming language\nRust is")
+  );
+  assert_eq!(control.binaries[1].section.as_deref(), Some("doc"));
+
+  // Writing it back out reads the same
+  assert_eq!(ControlFile::from_str(&control.to_string())?, control);
+
+  Ok(())
+}
+
+#[test]
+fn control_needs_package() {
+  let err =
+    ControlFile::from_str("Source: foo\n\nArchitecture: any\n").unwrap_err();
+  assert!(format!("{:#}", err).contains("no `Package` field"));
+}
+
+#[test]
+fn tests_control() -> eyre::Result<()> {
+  let tests = TestsControlFile::from_str(
+    "\
+Tests: smoke, upstream
+Depends: @, python3
+Restrictions: allow-stderr needs-root
+
+Test-Command: foo --version
+Features: test-name=version
+",
+  )?;
+
+  assert_eq!(tests.tests[0].tests, vec!["smoke", "upstream"]);
+  assert_eq!(tests.tests[0].depends.to_string(), "@, python3");
+  assert_eq!(
+    tests.tests[0].restrictions,
+    vec!["allow-stderr", "needs-root"]
+  );
+  assert_eq!(
+    tests.tests[1].test_command.as_deref(),
+    Some("foo --version")
+  );
+  assert_eq!(TestsControlFile::from_str(&tests.to_string())?, tests);
+
+  assert!(TestsControlFile::from_str("Depends: foo\n").is_err());
+  assert!(TestsControlFile::from_str("Tests: a\nTest-Command: b\n").is_err());
+
+  Ok(())
+}
+
+#[test]
+fn dsc_file() -> eyre::Result<()> {
+  let dsc = DscFile::from_str(
+    "\
+Format: 3.0 (quilt)
+Source: hello
+Binary: hello
+Architecture: any
+Version: 2.10-3
+Maintainer: Santiago Vila <sanvila@debian.org>
+Standards-Version: 4.6.2
+Build-Depends: debhelper-compat (= 13), help2man
+Package-List:
+ hello deb devel optional arch=any
+Checksums-Sha1:
+ f7bebf6f9c62a2295e889f66e05ce9bfaed9ace3 725946 hello_2.10.orig.tar.gz
+Checksums-Sha256:
+ 31e066137a962676e89f69d1b65382de95a7ef7d914b8cb956f41ea72e0f516b 725946 hello_2.10.orig.tar.gz
+Files:
+ 6cd0ffea3884a4e79330338dcc2987d6 725946 hello_2.10.orig.tar.gz
+",
+  )?;
+
+  assert_eq!(dsc.source, "hello");
+  assert_eq!(dsc.version.as_deref(), Some("2.10-3"));
+  assert_eq!(
+    dsc.build_depends.to_string(),
+    "debhelper-compat (= 13), help2man"
+  );
+  assert_eq!(
+    dsc.files,
+    vec![Checksum {
+      checksum: "6cd0ffea3884a4e79330338dcc2987d6".to_owned(),
+      size: 725946,
+      name: "hello_2.10.orig.tar.gz".to_owned(),
+    }]
+  );
+  assert_eq!(dsc.checksums_sha256[0].size, 725946);
+  assert_eq!(DscFile::from_str(&dsc.to_string())?, dsc);
+
+  assert!(
+    DscFile::from_str("Source: a\nFiles:\n abc notanumber a.tar\n").is_err()
+  );
+
+  Ok(())
+}
+
+#[test]
+fn changes_file() -> eyre::Result<()> {
+  let changes = ChangesFile::from_str(
+    "\
+Format: 1.8
+Date: Sun, 04 Jun 2023 12:00:00 +0200
+Source: hello
+Binary: hello hello-dbgsym
+Architecture: source amd64
+Version: 2.10-3
+Distribution: unstable
+Urgency: medium
+Maintainer: Santiago Vila <sanvila@debian.org>
+Description:
+ hello      - example package based on GNU hello
+Closes: 871622 893083
+Changes:
+ hello (2.10-3) unstable; urgency=medium
+ .
+   * Raise debhelper compat level to 13.
+Files:
+ 6cd0ffea3884a4e79330338dcc2987d6 725946 devel optional hello_2.10.orig.tar.gz
+",
+  )?;
+
+  assert_eq!(changes.binary, vec!["hello", "hello-dbgsym"]);
+  assert_eq!(changes.closes, vec!["871622", "893083"]);
+  assert_eq!(
+    changes.files,
+    vec![ChangesFileEntry {
+      md5: "6cd0ffea3884a4e79330338dcc2987d6".to_owned(),
+      size: 725946,
+      section: "devel".to_owned(),
+      priority: "optional".to_owned(),
+      name: "hello_2.10.orig.tar.gz".to_owned(),
+    }]
+  );
+  assert_eq!(ChangesFile::from_str(&changes.to_string())?, changes);
+
+  Ok(())
+}
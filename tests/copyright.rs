@@ -0,0 +1,197 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::deb822::{
+  Deb822File, DuplicateFields, copyright::CopyrightFile,
+};
+
+/// `Files-Included` punches holes in `Files-Excluded`.
+#[test]
+fn included_overrides_excluded() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(
+    "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded:
+ vendor/*
+ *.min.js
+Files-Included:
+ vendor/foo-legit
+",
+  )?;
+
+  assert!(copyright.is_path_excluded("vendor/bar-1.0/src/lib.rs"));
+  assert!(copyright.is_path_excluded("web/jquery.min.js"));
+  assert!(!copyright.is_path_excluded("vendor/foo-legit"));
+  assert!(!copyright.is_path_excluded("vendor/foo-legit/src/lib.rs"));
+  assert!(copyright.is_path_excluded("vendor/foo-legitimate/src/lib.rs"));
+  // Including something that was never excluded does nothing
+  assert!(!copyright.is_path_excluded("src/main.rs"));
+
+  Ok(())
+}
+
+/// The test project's copyright file has comments in the middle of fields.
+#[test]
+fn fake_project() -> eyre::Result<()> {
+  let source =
+    std::fs::read_to_string("tests/libfakeproject/debian/copyright")?;
+  let copyright = CopyrightFile::from_str(&source)?;
+
+  assert!(copyright.is_path_excluded("src/illegal.py"));
+  assert!(!copyright.is_path_excluded("src/main.py"));
+
+  Ok(())
+}
+
+const FULL: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: foo
+Upstream-Contact: Jane Doe <jane@example.com>
+ bugs@example.com
+Source: https://example.com/foo
+Disclaimer: not part of Debian
+Comment: first line
+ second line
+License: GPL-2+
+Copyright: 2020 Jane Doe
+Files-Excluded: vendor/*
+
+Files: *
+Copyright: 2020 Jane Doe
+ 2021 John Doe
+License: GPL-2+
+
+Files: src/bundled/* include/bundled.h
+Copyright: 2019 Someone Else
+License: MIT
+ Permission is hereby granted...
+Comment: vendored
+
+License: GPL-2+
+ This program is free software...
+Comment: see /usr/share/common-licenses/GPL-2
+";
+
+/// All the paragraphs and fields of DEP-5 get picked up.
+#[test]
+fn full_model() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(FULL)?;
+
+  let header = &copyright.header;
+  assert_eq!(header.upstream_name.as_deref(), Some("foo"));
+  assert_eq!(
+    header.upstream_contact,
+    vec!["Jane Doe <jane@example.com>", "bugs@example.com"]
+  );
+  assert_eq!(header.source.as_deref(), Some("https://example.com/foo"));
+  assert_eq!(header.disclaimer.as_deref(), Some("not part of Debian"));
+  assert_eq!(header.comment.as_deref(), Some("first line\nsecond line"));
+  assert_eq!(header.license.as_ref().map(|l| &*l.name), Some("GPL-2+"));
+  assert_eq!(header.copyright, vec!["2020 Jane Doe"]);
+  assert_eq!(copyright.excludes().len(), 1);
+
+  assert_eq!(copyright.files.len(), 2);
+  let bundled = &copyright.files[1];
+  assert_eq!(
+    bundled
+      .files
+      .iter()
+      .map(|g| g.to_string())
+      .collect::<Vec<_>>(),
+    vec!["src/bundled/*", "include/bundled.h"]
+  );
+  let license = bundled.license.as_ref().unwrap();
+  assert_eq!(license.name, "MIT");
+  assert_eq!(license.text, vec!["Permission is hereby granted..."]);
+  assert_eq!(bundled.comment.as_deref(), Some("vendored"));
+  assert_eq!(copyright.files[0].copyright.len(), 2);
+
+  assert_eq!(copyright.licenses.len(), 1);
+  let gpl = copyright.license_text("GPL-2+").unwrap();
+  assert_eq!(gpl.license.text, vec!["This program is free software..."]);
+  assert!(copyright.license_text("MIT").is_none());
+
+  Ok(())
+}
+
+/// The full model can be written out and read back in.
+#[test]
+fn full_round_trip() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(FULL)?;
+  let written = copyright.to_string();
+  assert_eq!(CopyrightFile::from_str(&written)?, copyright);
+  Ok(())
+}
+
+/// Without a header paragraph, the first `Files` paragraph still counts.
+#[test]
+fn no_header() -> eyre::Result<()> {
+  let source = "\
+Files: *
+Copyright: me
+License: MIT
+
+Files: vendor/*
+Copyright: them
+License: BSD-3-clause
+";
+  let copyright = CopyrightFile::from_str(source)?;
+  assert_eq!(copyright.header.format, None);
+  assert_eq!(copyright.files.len(), 2);
+  let license = copyright
+    .lookup("src/main.rs")
+    .and_then(|p| p.license.as_ref());
+  assert_eq!(license.map(|l| l.name.as_str()), Some("MIT"));
+  assert_eq!(copyright.to_string(), source);
+  Ok(())
+}
+
+/// The last matching `Files` paragraph is the one that applies.
+#[test]
+fn lookup_last_match_wins() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(FULL)?;
+  let license_of = |path: &str| {
+    copyright
+      .lookup(path)
+      .and_then(|paragraph| paragraph.license.as_ref())
+      .map(|license| license.name.clone())
+  };
+
+  assert_eq!(license_of("src/main.c").as_deref(), Some("GPL-2+"));
+  assert_eq!(
+    license_of("src/bundled/zlib/inflate.c").as_deref(),
+    Some("MIT")
+  );
+  assert_eq!(license_of("include/bundled.h").as_deref(), Some("MIT"));
+  assert_eq!(license_of("include/other.h").as_deref(), Some("GPL-2+"));
+
+  let no_catch_all = CopyrightFile::from_str(
+    "Format: whatever\n\nFiles: src/*\nCopyright: me\nLicense: MIT\n",
+  )?;
+  assert!(no_catch_all.lookup("src/a.c").is_some());
+  assert!(no_catch_all.lookup("docs/a.md").is_none());
+
+  Ok(())
+}
+
+/// Lowercase field names count, and in lenient mode every copy of
+/// `Files-Excluded` does.
+#[test]
+fn field_name_case() -> eyre::Result<()> {
+  let source = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+files-excluded: vendor/*
+FILES-EXCLUDED: docs/*
+";
+  assert!(CopyrightFile::from_str(source).is_err());
+
+  let copyright =
+    CopyrightFile::new(Deb822File::parse(source, DuplicateFields::Keep)?)?;
+  let excludes: Vec<String> = copyright
+    .excludes()
+    .iter()
+    .map(|glob| glob.to_string())
+    .collect();
+  assert_eq!(excludes, vec!["vendor/*", "docs/*"]);
+
+  Ok(())
+}
@@ -0,0 +1,50 @@
+use std::{path::PathBuf, str::FromStr};
+
+use deb_strip_copyright::{
+  coverage::check_coverage, deb822::copyright::CopyrightFile,
+};
+
+mod common;
+use common::make_tar_xz;
+
+/// Find uncovered files and unused globs, ignoring excluded files and
+/// directories.
+#[test]
+fn uncovered_and_unused() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(
+    "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded: vendor/*
+
+Files: src/*
+Copyright: me
+License: MIT
+
+Files: README.md docs/old/*
+Copyright: me
+License: CC0-1.0
+",
+  )?;
+  let input = make_tar_xz(&[
+    "src/",
+    "src/main.rs",
+    "README.md",
+    "build.rs",
+    "vendor/",
+    "vendor/blob.so",
+    "tools/",
+    "tools/gen.py",
+  ])?;
+
+  let report = check_coverage(&copyright, input.as_slice())?;
+  assert_eq!(
+    report.uncovered,
+    vec![PathBuf::from("build.rs"), PathBuf::from("tools/gen.py")]
+  );
+  assert_eq!(report.unused_globs.len(), 1);
+  assert_eq!(report.unused_globs[0].paragraph, 1);
+  assert_eq!(report.unused_globs[0].glob.to_string(), "docs/old/*");
+  assert!(!report.is_clean());
+
+  Ok(())
+}
@@ -0,0 +1,369 @@
+use std::{ops::RangeBounds, str::FromStr};
+
+use deb_strip_copyright::deb822::{
+  Deb822File, DuplicateFields, Field, FieldKind, Stanza,
+  copyright::CopyrightFile,
+};
+use eyre::bail;
+use fastrand::Rng;
+
+/// Random text that can be a field value: no leading or trailing whitespace,
+/// and no `#` so it can't be confused with a comment.
+fn gen_value(rng: &mut Rng, size: impl RangeBounds<usize>) -> String {
+  let sz = rng.usize(size);
+  let s: String = std::iter::repeat_with(|| match rng.u8(0..10) {
+    0 => ' ',
+    1 => rng
+      .choice(['.', ',', ':', '*', '<', '>', '(', '/'])
+      .unwrap(),
+    _ => rng.alphanumeric(),
+  })
+  .take(sz)
+  .collect();
+  s.trim().to_owned()
+}
+
+fn gen_field(rng: &mut Rng) -> Field<'static> {
+  let same_line_value = if rng.bool() {
+    Some(gen_value(rng, 1..40))
+      .filter(|s| !s.is_empty())
+      .map(Into::into)
+  } else {
+    None
+  };
+  // Empty lines are written as ` .`, so a line that really is just `.`
+  // would read back as empty
+  let list_values = (0..rng.usize(0..5))
+    .map(|_| match rng.u8(0..4) {
+      0 => String::new(),
+      _ => gen_value(rng, 1..60),
+    })
+    .filter(|s| s != ".")
+    .map(Into::into)
+    .collect();
+  Field {
+    same_line_value,
+    list_values,
+    span: None,
+    value_spans: Vec::new(),
+  }
+}
+
+fn gen_file(rng: &mut Rng) -> Deb822File<'static> {
+  let stanzas = (0..rng.usize(1..6))
+    .map(|_| {
+      let mut stanza = Stanza::new();
+      for field_idx in 0..rng.usize(1..8) {
+        let name = format!("Field-{}{}", rng.alphanumeric(), field_idx);
+        stanza.insert(name, gen_field(rng));
+      }
+      stanza
+    })
+    .collect();
+  Deb822File::new(stanzas)
+}
+
+/// Writing a file and parsing it again gives the same file.
+#[test]
+fn round_trip() -> eyre::Result<()> {
+  let mut rng = Rng::with_seed(822);
+  let mut empty_lines = 0;
+  for _ in 0..1000 {
+    let file = gen_file(&mut rng);
+    empty_lines += file
+      .stanzas()
+      .iter()
+      .flat_map(|stanza| stanza.iter())
+      .flat_map(|(_, field)| &field.list_values)
+      .filter(|line| line.is_empty())
+      .count();
+    let written = file.to_string();
+    let reparsed = Deb822File::from_str(&written)?;
+    if reparsed != file {
+      bail!(
+        "{:?} was written as {:?} and read back as {:?}",
+        file,
+        written,
+        reparsed
+      );
+    }
+  }
+  assert!(
+    empty_lines > 0,
+    "no empty continuation lines were generated"
+  );
+  Ok(())
+}
+
+/// Fields come back out in the order they went in, with the usual
+/// continuation-line conventions.
+#[test]
+fn writer_format() -> eyre::Result<()> {
+  let mut stanza = Stanza::new();
+  stanza.insert(
+    "Source".to_owned(),
+    Field {
+      same_line_value: Some("foo".into()),
+      list_values: Vec::new(),
+      span: None,
+      value_spans: Vec::new(),
+    },
+  );
+  stanza.insert(
+    "Description".to_owned(),
+    Field {
+      same_line_value: Some("short".into()),
+      list_values: vec!["para one".into(), "".into(), "para two".into()],
+      span: None,
+      value_spans: Vec::new(),
+    },
+  );
+  stanza.insert(
+    "Files".to_owned(),
+    Field {
+      same_line_value: None,
+      list_values: vec!["a".into(), "b".into()],
+      span: None,
+      value_spans: Vec::new(),
+    },
+  );
+  let file = Deb822File::new(vec![stanza.clone(), stanza]);
+
+  assert_eq!(
+    file.to_string(),
+    "\
+Source: foo
+Description: short
+ para one
+ .
+ para two
+Files:
+ a
+ b
+
+Source: foo
+Description: short
+ para one
+ .
+ para two
+Files:
+ a
+ b
+"
+  );
+  Ok(())
+}
+
+/// Copyright files survive being written out and read back in.
+#[test]
+fn copyright_round_trip() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(
+    "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded: vendor/* *.min.js
+ weird\\*name
+Files-Included: vendor/keep-me
+",
+  )?;
+  let written = copyright.to_string();
+  assert_eq!(
+    written,
+    "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded:
+ vendor/*
+ *.min.js
+ weird\\*name
+Files-Included: vendor/keep-me
+"
+  );
+  assert_eq!(CopyrightFile::from_str(&written)?, copyright);
+  Ok(())
+}
+
+/// Field names are case-insensitive, so names that differ only in case
+/// are duplicates too.
+#[test]
+fn duplicate_fields() -> eyre::Result<()> {
+  let deb = Deb822File::from_str("Source: foo\nHomePage: a\n")?;
+  assert!(deb.stanzas()[0].get("homepage").is_some());
+  assert!(deb.stanzas()[0].get("Homepage").is_some());
+
+  let source = "Source: foo\nHomepage: a\nhomepage: b\n";
+  let err = Deb822File::from_str(source).unwrap_err();
+  assert!(
+    format!("{:#}", err).contains("differs only in case from Homepage"),
+    "{:#}",
+    err
+  );
+  assert!(Deb822File::from_str("A: 1\nA: 2\n").is_err());
+
+  let deb = Deb822File::parse(source, DuplicateFields::Keep)?;
+  let stanza = &deb.stanzas()[0];
+  assert_eq!(stanza.len(), 3);
+  assert_eq!(
+    stanza.get("HOMEPAGE").unwrap().same_line_value.as_deref(),
+    Some("a")
+  );
+  let all: Vec<_> = stanza
+    .get_all("Homepage")
+    .map(|field| field.same_line_value.as_deref().unwrap())
+    .collect();
+  assert_eq!(all, vec!["a", "b"]);
+  // Both copies are written back out
+  assert_eq!(deb.to_string(), source);
+
+  Ok(())
+}
+
+/// ` .` is an empty line, extra indentation is kept, and a line of only
+/// whitespace ends the stanza.
+#[test]
+fn continuation_lines() -> eyre::Result<()> {
+  let deb = Deb822File::from_str(
+    "License: MIT
+  Permission is hereby granted:
+ .
+    1. indented more
+\t.
+  the end
+ \t
+Files: *
+Build-Depends: foo,
+   bar
+",
+  )?;
+  assert_eq!(deb.stanzas().len(), 2);
+
+  let license = deb.stanzas()[0].get("License").unwrap();
+  assert_eq!(
+    license.list_values,
+    vec![
+      "Permission is hereby granted:",
+      "",
+      "  1. indented more",
+      "",
+      "the end"
+    ]
+  );
+  assert_eq!(
+    license.value(FieldKind::Multiline),
+    "MIT\nPermission is hereby granted:\n\n  1. indented more\n\nthe end"
+  );
+
+  let depends = deb.stanzas()[1].get("Build-Depends").unwrap();
+  assert_eq!(depends.value(FieldKind::Folded), "foo, bar");
+  assert_eq!(
+    deb.stanzas()[1]
+      .get("Files")
+      .unwrap()
+      .value(FieldKind::Simple),
+    "*"
+  );
+
+  // Writing it back out reads the same
+  assert_eq!(Deb822File::from_str(&deb.to_string())?, deb);
+
+  Ok(())
+}
+
+#[test]
+fn spans() -> eyre::Result<()> {
+  let source = "\
+# comment
+Source: foo
+Description: short
+ long
+ .
+ more
+
+Files: *
+Copyright: me
+";
+  let deb = Deb822File::from_str(source)?;
+
+  let first = deb.stanzas()[0].span().unwrap();
+  assert_eq!((first.line, first.column, first.offset), (2, 1, 10));
+  assert_eq!(
+    first.text(source),
+    "Source: foo\nDescription: short\n long\n .\n more"
+  );
+  let description = deb.stanzas()[0].get("Description").unwrap();
+  assert_eq!(
+    description.span.unwrap().text(source),
+    "Description: short\n long\n .\n more"
+  );
+  let copyright = deb.stanzas()[1].get("Copyright").unwrap().span.unwrap();
+  assert_eq!((copyright.line, copyright.column), (9, 1));
+  assert_eq!(copyright.text(source), "Copyright: me");
+
+  // Spans don't count for equality
+  assert_eq!(Deb822File::from_str(&deb.to_string())?, deb);
+
+  Ok(())
+}
+
+/// Errors show the line, with carets under the problem.
+#[test]
+fn error_snippet() {
+  let err = Deb822File::from_str("Source: foo\nSource: bar\n").unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "\
+duplicate key Source (the first one is at 1:1)
+ --> 2:1
+  |
+2 | Source: bar
+  | ^^^^^^"
+  );
+
+  let err =
+    Deb822File::from_str("A: 1\n\tB: 2\n\n\tnot a field\n").unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "\
+field header must not start with whitespace
+ --> 4:1
+  |
+4 | \tnot a field
+  | ^^^^^^^^^^^^"
+  );
+}
+
+/// Broken stanzas are skipped, and the rest still parse.
+#[test]
+fn recovery() {
+  let source = "\
+A: 1
+
+B: 2
+not a field
+C: 3
+
+D: 4
+D: 5
+
+E: 6
+";
+  let (deb, errors) =
+    Deb822File::parse_recovering(source, DuplicateFields::Error);
+  let names: Vec<&str> = deb
+    .stanzas()
+    .iter()
+    .flat_map(|stanza| stanza.iter().map(|(name, _)| name))
+    .collect();
+  assert_eq!(names, vec!["A", "E"]);
+  let positions: Vec<(usize, usize)> = errors
+    .iter()
+    .map(|error| (error.span.line, error.span.column))
+    .collect();
+  assert_eq!(positions, vec![(4, 1), (8, 1)]);
+
+  // The normal parser stops at the first one
+  let err = Deb822File::from_str(source).unwrap_err();
+  assert_eq!(err.to_string(), errors[0].to_string());
+
+  // Nothing to recover from
+  let (_, errors) = Deb822File::parse_recovering("A: 1\n", Default::default());
+  assert!(errors.is_empty());
+}
@@ -0,0 +1,79 @@
+use std::{path::Path, str::FromStr};
+
+use deb_strip_copyright::{
+  deb822::copyright::CopyrightFile,
+  filter::{self, GlobList, PathFilter},
+  strip::EntryInfo,
+};
+
+const COPYRIGHT: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded: vendor/*
+";
+
+fn excluded(filter: impl PathFilter, path: &str) -> bool {
+  let path = Path::new(path);
+  filter.is_excluded(&EntryInfo {
+    path,
+    archive_path: path,
+    header: &tar::Header::new_gnu(),
+    size: 0,
+  })
+}
+
+/// Combinators behave like their boolean counterparts.
+#[test]
+fn combinators() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(COPYRIGHT)?;
+  let blobs = GlobList::from_str("*.bin *.so")?;
+
+  assert!(excluded(&copyright, "vendor/foo.rs"));
+  assert!(!excluded(&copyright, "src/foo.bin"));
+
+  let either = (&copyright).or(&blobs);
+  assert!(excluded(&either, "vendor/foo.rs"));
+  assert!(excluded(&either, "src/foo.bin"));
+  assert!(!excluded(&either, "src/foo.rs"));
+
+  let both = (&copyright).and(&blobs);
+  assert!(excluded(&both, "vendor/foo.bin"));
+  assert!(!excluded(&both, "vendor/foo.rs"));
+  assert!(!excluded(&both, "src/foo.bin"));
+
+  let inverted = (&copyright).not();
+  assert!(!excluded(&inverted, "vendor/foo.rs"));
+  assert!(excluded(&inverted, "src/foo.rs"));
+
+  Ok(())
+}
+
+/// Closures and boxed filters can be mixed in.
+#[test]
+fn closures_and_boxes() -> eyre::Result<()> {
+  let copyright = CopyrightFile::from_str(COPYRIGHT)?;
+  let long_names =
+    filter::from_fn(|entry: &EntryInfo| entry.path.as_os_str().len() > 20);
+  let boxed: Box<dyn PathFilter> = Box::new(copyright.or(long_names));
+
+  assert!(excluded(&boxed, "vendor/x"));
+  assert!(excluded(&boxed, "src/a_really_long_file_name.rs"));
+  assert!(!excluded(&boxed, "src/short.rs"));
+
+  Ok(())
+}
+
+/// `--exclude-from` files allow comments and several globs per line.
+#[test]
+fn glob_list_parsing() -> eyre::Result<()> {
+  let list = GlobList::from_str(
+    "# blobs we can't ship\n*.bin\n\n  # indented comment\ndocs/*.pdf *.jar\n",
+  )?;
+
+  assert!(excluded(&list, "firmware/thing.bin"));
+  assert!(excluded(&list, "docs/manual.pdf"));
+  assert!(excluded(&list, "lib/old.jar"));
+  assert!(!excluded(&list, "docs/manual.md"));
+  assert!(GlobList::from_str("ok/* foo**").is_err());
+
+  Ok(())
+}
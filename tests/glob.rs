@@ -1,6 +1,6 @@
 use std::{ops::RangeBounds, str::FromStr};
 
-use deb_strip_copyright::glob::Glob;
+use deb_strip_copyright::glob::{Glob, set::GlobSet};
 use eyre::bail;
 use fastrand::Rng;
 
@@ -122,3 +122,144 @@ fn escape() -> eyre::Result<()> {
 
   Ok(())
 }
+
+/// Writing a glob out gives something that parses back to the same glob.
+#[test]
+fn display_round_trip() -> eyre::Result<()> {
+  let mut rng = Rng::with_seed(0x61a5);
+  for _ in 0..1000 {
+    let len = rng.usize(1..20);
+    let glob_str: String = std::iter::repeat_with(|| match rng.u8(0..8) {
+      0 => "\\*".to_owned(),
+      1 => "\\?".to_owned(),
+      2 => "\\\\".to_owned(),
+      3 => "?".to_owned(),
+      _ => rng.alphanumeric().to_string(),
+    })
+    .take(len)
+    .collect();
+
+    let glob = Glob::from_str(&glob_str)?;
+    let reparsed = Glob::from_str(&glob.to_string())?;
+    if reparsed != glob {
+      bail!("glob {:?} was written as {:?}", &glob, glob.to_string());
+    }
+  }
+
+  Ok(())
+}
+
+/// Names that aren't UTF-8 are matched by their bytes, not by what
+/// they'd look like after a lossy conversion.
+#[test]
+fn non_utf8() -> eyre::Result<()> {
+  // `café` in Latin-1
+  let latin1: &[u8] = b"docs/caf\xe9";
+
+  assert!(Glob::from_str("docs/*")?.matches_bytes(latin1));
+  assert!(Glob::from_str("docs/caf?")?.matches_bytes(latin1));
+  assert!(Glob::from_str("*/caf?.txt")?.matches_bytes(b"docs/caf\xe9.txt"));
+  assert!(Glob::from_str("*.bin")?.matches_bytes(b"\xff\xfe.bin"));
+  assert!(Glob::from_str("caf?")?.matches_bytes(b"caf\xe9/README"));
+
+  // Neither the UTF-8 spelling nor the replacement character matches
+  assert!(!Glob::from_str("docs/café")?.matches_bytes(latin1));
+  assert!(!Glob::from_str("docs/caf\u{fffd}")?.matches_bytes(latin1));
+
+  // `?` still takes a whole character when there is one
+  assert!(Glob::from_str("caf?.txt")?.matches("café.txt"));
+  assert!(!Glob::from_str("caf??.txt")?.matches("café.txt"));
+
+  // Paths work the same way
+  #[cfg(unix)]
+  {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::Path};
+    let path = Path::new(OsStr::from_bytes(latin1));
+    assert!(Glob::from_str("docs/caf?")?.matches(path));
+    assert!(!Glob::from_str("docs/caf\u{fffd}")?.matches(path));
+  }
+
+  Ok(())
+}
+
+/// A random glob over a small alphabet, so that globs and paths share
+/// lots of literals. `None` if it came out as an invalid glob.
+fn gen_glob(rng: &mut Rng) -> Option<Glob> {
+  let len = rng.usize(1..8);
+  let glob_str: String = std::iter::repeat_with(|| match rng.u8(0..10) {
+    0 => '*',
+    1 => '?',
+    2 => '/',
+    3 => '.',
+    _ => rng.choice(['a', 'b', 'c']).unwrap(),
+  })
+  .take(len)
+  .collect();
+  Glob::from_str(&glob_str).ok()
+}
+
+/// A set matches exactly the globs that match one at a time.
+#[test]
+fn set_agrees_with_globs() -> eyre::Result<()> {
+  let mut rng = Rng::with_seed(0x5e7);
+  for _ in 0..300 {
+    let count = rng.usize(0..30);
+    let globs: Vec<Glob> = std::iter::repeat_with(|| gen_glob(&mut rng))
+      .flatten()
+      .take(count)
+      .collect();
+    let set = GlobSet::new(globs.clone());
+    for _ in 0..100 {
+      let len = rng.usize(0..12);
+      let path: String = std::iter::repeat_with(|| {
+        rng.choice(['a', 'b', 'c', '/', '.', 'é']).unwrap()
+      })
+      .take(len)
+      .collect();
+      let expected: Vec<usize> = globs
+        .iter()
+        .enumerate()
+        .filter(|(_, glob)| glob.matches(&path))
+        .map(|(idx, _)| idx)
+        .collect();
+      let got = set.matches(&path);
+      if got != expected {
+        bail!(
+          "set of {:?} matched {:?} with {:?}, not {:?}",
+          &globs,
+          &path,
+          got,
+          expected
+        );
+      }
+      if set.is_match(&path) == expected.is_empty() {
+        bail!(
+          "set of {:?} is_match {:?} disagrees with {:?}",
+          &globs,
+          &path,
+          expected
+        );
+      }
+    }
+  }
+
+  Ok(())
+}
+
+#[test]
+fn set_matches() -> eyre::Result<()> {
+  let set: GlobSet = ["vendor/foo-*", "*.min.js", "vendor", "?", "docs/caf?"]
+    .into_iter()
+    .map(Glob::from_str)
+    .collect::<eyre::Result<_>>()?;
+
+  assert_eq!(set.matches("vendor/foo-1.0/src/lib.rs"), vec![0, 2]);
+  assert_eq!(set.matches("web/jquery.min.js"), vec![1]);
+  assert_eq!(set.matches("x"), vec![3]);
+  assert_eq!(set.matches_bytes(b"docs/caf\xe9"), vec![4]);
+  assert!(set.matches("src/main.rs").is_empty());
+  assert!(!set.is_match("vendors"));
+  assert!(!GlobSet::default().is_match("anything"));
+
+  Ok(())
+}
@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::license::{LicenseExpr, LicenseName, debian_to_spdx};
+
+fn spdx(expr: &str) -> eyre::Result<String> {
+  LicenseExpr::from_str(expr)?.to_spdx()
+}
+
+/// Debian short names become SPDX identifiers.
+#[test]
+fn short_names() {
+  for (debian, spdx) in [
+    ("GPL-2", "GPL-2.0-only"),
+    ("GPL-2+", "GPL-2.0-or-later"),
+    ("GPL-3.0+", "GPL-3.0-or-later"),
+    ("LGPL-2.1", "LGPL-2.1-only"),
+    ("lgpl-2.1+", "LGPL-2.1-or-later"),
+    ("AGPL-3+", "AGPL-3.0-or-later"),
+    ("GFDL-1.3+", "GFDL-1.3-or-later"),
+    ("GFDL-NIV-1.2", "GFDL-1.2-no-invariants-only"),
+    ("GPL-2.0-only", "GPL-2.0-only"),
+    ("Expat", "MIT"),
+    ("BSD-3-clause", "BSD-3-Clause"),
+    ("Apache-2.0", "Apache-2.0"),
+    ("MPL-1.1+", "MPL-1.1+"),
+    ("Zope-2.1", "ZPL-2.1"),
+    ("CC-BY-SA-4.0", "CC-BY-SA-4.0"),
+    ("cc0-1.0", "CC0-1.0"),
+    ("Perl", "Artistic-1.0-Perl OR GPL-1.0-or-later"),
+  ] {
+    assert_eq!(debian_to_spdx(debian).as_deref(), Some(spdx), "{}", debian);
+  }
+  for unknown in ["GPL-4", "LGPL-1", "public-domain", "CC-BY-5.0", ""] {
+    assert_eq!(debian_to_spdx(unknown), None, "{}", unknown);
+  }
+}
+
+/// Precedence and commas work out like the spec says.
+#[test]
+fn expressions() -> eyre::Result<()> {
+  assert_eq!(spdx("GPL-2+ or MIT")?, "GPL-2.0-or-later OR MIT");
+  assert_eq!(
+    spdx("GPL-2+ or Artistic-2.0, and BSD-3-clause")?,
+    "(GPL-2.0-or-later OR Artistic-2.0) AND BSD-3-Clause"
+  );
+  assert_eq!(
+    spdx("MIT and BSD-2-clause or Apache-2.0")?,
+    "MIT AND BSD-2-Clause OR Apache-2.0"
+  );
+  assert_eq!(
+    spdx("Apache-2.0 with LLVM-exception")?,
+    "Apache-2.0 WITH LLVM-exception"
+  );
+  assert_eq!(
+    spdx("GPL-3+ with Font exception or OFL-1.1")?,
+    "GPL-3.0-or-later WITH Font-exception-2.0 OR OFL-1.1"
+  );
+  assert_eq!(
+    spdx("Perl and MIT")?,
+    "(Artistic-1.0-Perl OR GPL-1.0-or-later) AND MIT"
+  );
+  assert_eq!(spdx("MIT OR Apache-2.0")?, "MIT OR Apache-2.0");
+  assert_eq!(
+    spdx("MIT and (BSD-2-clause or Apache-2.0)")?,
+    "MIT AND (BSD-2-Clause OR Apache-2.0)"
+  );
+  Ok(())
+}
+
+/// Names with no SPDX equivalent are reported.
+#[test]
+fn unknown_names() -> eyre::Result<()> {
+  let expr =
+    LicenseExpr::from_str("GPL-2+ with OpenSSL exception or public-domain")?;
+  assert_eq!(
+    expr.unknown_names(),
+    vec!["GPL-2+ with OpenSSL exception", "public-domain"]
+  );
+  assert!(expr.to_spdx().is_err());
+  assert_eq!(
+    expr.to_spdx_lossy(),
+    "LicenseRef-GPL-2--with-OpenSSL-exception OR LicenseRef-public-domain"
+  );
+  Ok(())
+}
+
+/// Malformed expressions are rejected.
+#[test]
+fn syntax_errors() {
+  for bad in [
+    "",
+    "or",
+    "MIT or",
+    "MIT, BSD-3-clause",
+    "MIT with",
+    "MIT MIT",
+    "(MIT",
+    "MIT)",
+    "()",
+  ] {
+    assert!(LicenseExpr::from_str(bad).is_err(), "{:?}", bad);
+  }
+}
+
+/// Writing an expression back out in Debian syntax keeps its meaning.
+#[test]
+fn display_round_trip() -> eyre::Result<()> {
+  for source in [
+    "MIT",
+    "GPL-2+ or MIT",
+    "GPL-2+ or Artistic-2.0, and BSD-3-clause",
+    "A or B, and C, or D",
+    "GPL-2+ with OpenSSL exception and Apache-2.0 with LLVM-exception",
+    "A or (B or C, and D)",
+    "A, and (B or C, and D, or E)",
+  ] {
+    let expr = LicenseExpr::from_str(source)?;
+    assert_eq!(expr.to_string(), source);
+    assert_eq!(LicenseExpr::from_str(&expr.to_string())?, expr);
+  }
+
+  // Commas alone can't write this one the right way round
+  let name = |name: &str| {
+    LicenseExpr::License(LicenseName {
+      name: name.to_owned(),
+      exception: None,
+    })
+  };
+  let expr = LicenseExpr::Or(vec![
+    name("A"),
+    LicenseExpr::And(vec![
+      LicenseExpr::Or(vec![name("B"), name("C")]),
+      name("D"),
+    ]),
+  ]);
+  assert_eq!(expr.to_string(), "A or (B or C, and D)");
+  assert_eq!(LicenseExpr::from_str(&expr.to_string())?, expr);
+  Ok(())
+}
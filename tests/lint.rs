@@ -0,0 +1,201 @@
+use deb_strip_copyright::lint::{Severity, lint};
+
+/// Render the diagnostics the same way the `lint` subcommand does,
+/// for easy comparison.
+fn lint_lines(source: &str) -> Vec<String> {
+  lint(source).iter().map(|d| d.to_string()).collect()
+}
+
+/// A correct file has nothing to say about it.
+#[test]
+fn clean_file() {
+  let source = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: foo
+Files-Excluded: vendor/*
+
+Files: *
+Copyright: me
+License: GPL-2+ or MIT
+
+Files: src/special.c
+Copyright: them
+License: BSD-3-clause
+ Redistribution and use...
+
+License: GPL-2+
+ This program is free software...
+
+License: MIT
+ Permission is hereby granted...
+";
+  assert_eq!(lint_lines(source), Vec::<String>::new());
+}
+
+/// Every kind of problem gets reported, with its position.
+#[test]
+fn everything_wrong() {
+  let source = "\
+Upstream-Name: foo
+Upstream-Nmae: typo
+
+Files: * src/**
+Copyright: me
+License: GPL-2+ with OpenSSL exception
+Files-Excluded: vendor/*
+
+Files: docs/* *
+License: CC0-1.0
+X-Custom: allowed
+
+Comment: what is this paragraph
+";
+  assert_eq!(
+    lint_lines(source),
+    vec![
+      "1:1: error: header paragraph has no `Format` field",
+      "2:1: warning: unknown field `Upstream-Nmae` in header paragraph",
+      "7:1: error: `Files-Excluded` must be in the header paragraph",
+      "4:10: error: unsupported glob `src/**`: cannot have a `*` next to \
+       another wildcard",
+      "9:1: error: Files paragraph has no `Copyright` field",
+      "9:15: warning: duplicate glob `*` (first seen at 4:8)",
+      "13:1: error: paragraph has neither a `Files` nor a `License` field",
+      "6:10: error: license `GPL-2+` has no standalone License paragraph",
+      "10:10: error: license `CC0-1.0` has no standalone License paragraph",
+    ]
+  );
+}
+
+/// Syntax errors are reported as-is.
+#[test]
+fn syntax_error() {
+  let diagnostics = lint("Format: foo\nthis is not a field\n");
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0].severity, Severity::Error);
+  assert!(diagnostics[0].message.contains("could not find `:`"));
+  let span = diagnostics[0].span.unwrap();
+  assert_eq!((span.line, span.column), (2, 1));
+}
+
+/// Every syntax error is reported, not just the first.
+#[test]
+fn all_syntax_errors() {
+  let source = "\
+Format: foo
+this is not a field
+
+Files: *
+Files: again
+
+ License: indented
+";
+  assert_eq!(
+    lint_lines(source),
+    vec![
+      "2:1: error: could not find `:` in field header line",
+      "5:1: error: duplicate key Files (the first one is at 4:1)",
+      "7:1: error: field header must not start with whitespace",
+    ]
+  );
+}
+
+/// Known fields spelled with the wrong case still count, with a warning.
+#[test]
+fn field_name_case() {
+  let source = "\
+format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+files-excluded: vendor/*
+
+Files: *
+Copyright: me
+license: MIT
+ Permission is hereby granted...
+";
+  assert_eq!(
+    lint_lines(source),
+    vec![
+      "1:1: warning: field `format` is usually spelled `Format`",
+      "2:1: warning: field `files-excluded` is usually spelled \
+       `Files-Excluded`",
+      "6:1: warning: field `license` is usually spelled `License`",
+    ]
+  );
+}
+
+/// Simple fields can't be wrapped onto continuation lines.
+#[test]
+fn simple_field_on_one_line() {
+  let source = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: foo
+ bar
+
+Files: *
+Copyright: me
+License: MIT
+ Permission is hereby granted...
+";
+  assert_eq!(
+    lint_lines(source),
+    vec!["2:1: warning: `Upstream-Name` should be on one line"]
+  );
+}
+
+/// Positions come from the real parser, so comments and ` .` lines inside
+/// fields don't throw them off.
+#[test]
+fn comments_and_dots_in_fields() {
+  let source = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded:
+# vendored code
+ vendor/*
+ .
+\tvendor/*
+
+Files: *
+Copyright: me
+License: MIT
+ Permission is hereby granted...
+ .
+ The above copyright notice shall be included...
+";
+  assert_eq!(
+    lint_lines(source),
+    vec!["6:2: warning: duplicate glob `vendor/*` (first seen at 4:2)"]
+  );
+}
+
+/// Empty files, and files with nothing but blank lines and comments, are
+/// reported as empty rather than as syntax errors.
+#[test]
+fn empty_file() {
+  for source in ["", "\n\n", "  \n# just a comment\n"] {
+    assert_eq!(lint_lines(source), vec!["1:1: error: file is empty"]);
+  }
+}
+
+/// A file that starts straight in with a `Files` paragraph has no header,
+/// and that paragraph is still checked as a `Files` paragraph.
+#[test]
+fn no_header() {
+  let source = "\
+Files: *
+Copyright: me
+License: MIT
+ Permission is hereby granted...
+
+Files: vendor/*
+License: BSD-3-clause
+ Redistribution and use...
+";
+  assert_eq!(
+    lint_lines(source),
+    vec![
+      "1:1: error: file has no header paragraph before the first Files \
+       paragraph",
+      "6:1: error: Files paragraph has no `Copyright` field",
+    ]
+  );
+}
@@ -0,0 +1,145 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::deb822::{Deb822File, lossless::LosslessFile};
+use eyre::bail;
+use fastrand::Rng;
+
+fn round_trip(source: &str) -> eyre::Result<()> {
+  let parsed = LosslessFile::from_str(source)?;
+  let written = parsed.to_string();
+  if written != source {
+    bail!("{:?} was written back out as {:?}", source, written);
+  }
+  Ok(())
+}
+
+/// Awkward but valid files come back out byte-for-byte.
+#[test]
+fn byte_for_byte() -> eyre::Result<()> {
+  round_trip(&std::fs::read_to_string(
+    "tests/libfakeproject/debian/copyright",
+  )?)?;
+  for source in [
+    "",
+    "\n\n",
+    "Foo: bar",
+    "Foo: bar\n",
+    "Foo:bar   \n",
+    "Foo:\tbar\r\nBaz: quux\r\n\r\n",
+    "# leading comment\n\n\nFoo: bar\n",
+    "Foo:\n  indented\n\tindented with a tab\n .\n   \n",
+    "Foo: bar\n# comment\n    # indented comment\nBar: baz\n",
+    "A: 1\n\n   \n\t\nB: 2\n# trailing comment\n\n",
+    "A: 1\n\n\n\nB: 2\n\nC: 3",
+  ] {
+    round_trip(source)?;
+  }
+  Ok(())
+}
+
+/// Smash together random pieces of deb822 files, and make sure they
+/// survive.
+#[test]
+fn random_round_trip() -> eyre::Result<()> {
+  let pieces = [
+    "Field: value",
+    "Other-Field:value   ",
+    "Empty:",
+    " continuation",
+    "\tcontinuation",
+    " .",
+    "# comment",
+    "  # comment",
+    "",
+    "\r",
+  ];
+  let mut rng = Rng::with_seed(0xdeb);
+  for _ in 0..1000 {
+    let count = rng.usize(0..20);
+    let mut lines: Vec<&str> = Vec::new();
+    for _ in 0..count {
+      let piece = pieces[rng.usize(..pieces.len())];
+      // Continuations need something to continue
+      let continues_field = lines.last().is_some_and(|prev| {
+        !prev.trim().is_empty() && !prev.trim_start().starts_with('#')
+      });
+      if piece.starts_with([' ', '\t']) && !continues_field {
+        continue;
+      }
+      lines.push(piece);
+    }
+    round_trip(&lines.join("\n"))?;
+  }
+  Ok(())
+}
+
+/// Edits only change the field that was edited.
+#[test]
+fn edit_keeps_formatting() -> eyre::Result<()> {
+  let source = "\
+# Please keep this comment
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded:
+   vendor/*
+# why: blobs
+   firmware/*
+
+Files: *
+Copyright: someone
+License: MIT
+";
+  let mut file = LosslessFile::from_str(source)?;
+  let header = &mut file.stanzas[0];
+  let excluded = header.field_mut("Files-Excluded").unwrap();
+  assert_eq!(
+    excluded.list_values().collect::<Vec<_>>(),
+    vec!["vendor/*", "firmware/*"]
+  );
+  excluded.set_value(None, &["vendor/*", "firmware/*", "*.min.js"]);
+  header.push_field("Upstream-Name", Some("foo"), &[]);
+  assert!(file.stanzas[1].remove_field("Copyright"));
+
+  assert_eq!(
+    file.to_string(),
+    "\
+# Please keep this comment
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded:
+   vendor/*
+   firmware/*
+   *.min.js
+Upstream-Name: foo
+
+Files: *
+License: MIT
+"
+  );
+
+  // and it's still a valid file
+  file.to_deb822()?;
+
+  Ok(())
+}
+
+/// Things the normal parser rejects are rejected here too.
+#[test]
+fn rejects_garbage() {
+  assert!(LosslessFile::from_str(" indented: header\n").is_err());
+  assert!(LosslessFile::from_str("Foo: bar\nno colon here\n").is_err());
+}
+
+/// The lossless parser reads continuation lines and whitespace-only lines
+/// the same way the normal one does.
+#[test]
+fn agrees_with_normal_parser() -> eyre::Result<()> {
+  let source = "A: 1\n  x\n .\n   y\n \t\nB: 2\n";
+  let file = LosslessFile::from_str(source)?;
+  assert_eq!(file.stanzas.len(), 2);
+  assert_eq!(
+    file.stanzas[0].fields[0].list_values().collect::<Vec<_>>(),
+    vec!["x", "", " y"]
+  );
+  assert_eq!(file.to_deb822()?, Deb822File::from_str(source)?);
+
+  Ok(())
+}
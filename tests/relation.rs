@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::{
+  deb822::Deb822File,
+  relation::{
+    ArchRestriction, ProfileTerm, Relation, Relations, VersionConstraint,
+    VersionOp,
+  },
+};
+
+fn term(negated: bool, name: &str) -> ProfileTerm {
+  ProfileTerm {
+    negated,
+    name: name.to_owned(),
+  }
+}
+
+/// Every part of a relation at once.
+#[test]
+fn full_relation() -> eyre::Result<()> {
+  let relation = Relation::from_str(
+    "foo:any (>= 1:2.0~rc1) [amd64 !i386] <!nocheck> <stage1 cross>",
+  )?;
+  assert_eq!(
+    relation,
+    Relation {
+      name: "foo".to_owned(),
+      arch_qualifier: Some("any".to_owned()),
+      version: Some(VersionConstraint {
+        op: VersionOp::LaterOrEqual,
+        version: "1:2.0~rc1".to_owned(),
+      }),
+      architectures: vec![
+        ArchRestriction {
+          negated: false,
+          arch: "amd64".to_owned(),
+        },
+        ArchRestriction {
+          negated: true,
+          arch: "i386".to_owned(),
+        },
+      ],
+      profiles: vec![
+        vec![term(true, "nocheck")],
+        vec![term(false, "stage1"), term(false, "cross")],
+      ],
+    }
+  );
+
+  Ok(())
+}
+
+/// Commas, pipes, and every version operator.
+#[test]
+fn alternatives_and_ops() -> eyre::Result<()> {
+  let relations = Relations::from_str(
+    "a (<< 1), b (<= 2) | c (= 3),\n d (>= 4) | e (>> 5),",
+  )?;
+  assert_eq!(relations.len(), 3);
+  assert_eq!(relations.0[1].0.len(), 2);
+  let ops: Vec<VersionOp> = relations
+    .relations()
+    .map(|relation| relation.version.as_ref().unwrap().op)
+    .collect();
+  assert_eq!(
+    ops,
+    vec![
+      VersionOp::Earlier,
+      VersionOp::EarlierOrEqual,
+      VersionOp::Equal,
+      VersionOp::LaterOrEqual,
+      VersionOp::Later,
+    ]
+  );
+
+  Ok(())
+}
+
+/// Spacing is optional, and writing puts it back the normal way.
+#[test]
+fn normalizes_spacing() -> eyre::Result<()> {
+  let relations = Relations::from_str(
+    "foo(>=1.0)[linux-any]<!nocheck>|bar , ${misc:Depends}",
+  )?;
+  assert_eq!(
+    relations.to_string(),
+    "foo (>= 1.0) [linux-any] <!nocheck> | bar, ${misc:Depends}"
+  );
+  assert_eq!(Relations::from_str(&relations.to_string())?, relations);
+  assert!(Relations::from_str("")?.is_empty());
+
+  Ok(())
+}
+
+#[test]
+fn errors() {
+  for bad in [
+    "foo (>= )",
+    "foo (~ 1.0)",
+    "foo (>= 1.0",
+    "foo []",
+    "foo <>",
+    "foo bar",
+    "| foo",
+    "foo:",
+  ] {
+    assert!(Relations::from_str(bad).is_err(), "{:?} parsed", bad);
+  }
+}
+
+/// Fields can be parsed directly, across continuation lines.
+#[test]
+fn from_field() -> eyre::Result<()> {
+  let deb = Deb822File::from_str(
+    "\
+Source: foo
+Build-Depends: debhelper-compat (= 13),
+ python3:any,
+ libfoo-dev [!hurd-any] <!nodoc>
+",
+  )?;
+  let relations = deb.stanzas()[0].get("Build-Depends").unwrap().relations()?;
+  let names: Vec<&str> = relations
+    .relations()
+    .map(|relation| relation.name.as_str())
+    .collect();
+  assert_eq!(names, vec!["debhelper-compat", "python3", "libfoo-dev"]);
+
+  Ok(())
+}
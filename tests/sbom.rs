@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use deb_strip_copyright::{
+  deb822::copyright::CopyrightFile,
+  sbom::{SbomFormat, SpdxDocument},
+  strip::Stripper,
+};
+
+mod common;
+use common::make_tar_xz;
+
+const COPYRIGHT: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: proj
+Files-Excluded: vendor/*
+
+Files: *
+Copyright: 2024 Someone
+License: MIT
+
+Files: src/weird.c
+Copyright: 2020 Someone Else
+License: Weird
+
+License: Weird
+ You may do weird things.
+";
+
+fn document() -> eyre::Result<SpdxDocument> {
+  let input =
+    make_tar_xz(&["README.md", "src/", "src/weird.c", "vendor/foo.c"])?;
+  let copyright = CopyrightFile::from_str(COPYRIGHT)?;
+  let report = Stripper::new(&copyright)
+    .checksums(true)
+    .dry_run(input.as_slice())?;
+  Ok(SpdxDocument::new("proj_1.0", &copyright, &report.files))
+}
+
+/// Only kept regular files are listed, each under its own license.
+#[test]
+fn files_and_licenses() -> eyre::Result<()> {
+  let doc = document()?;
+
+  let files: Vec<(&str, &str, &str)> = doc
+    .files
+    .iter()
+    .map(|file| {
+      (
+        file.name.as_str(),
+        file.license_concluded.as_str(),
+        file.copyright_text.as_str(),
+      )
+    })
+    .collect();
+  assert_eq!(
+    files,
+    vec![
+      ("./README.md", "MIT", "2024 Someone"),
+      ("./src/weird.c", "LicenseRef-Weird", "2020 Someone Else"),
+    ]
+  );
+  // sha256sum of "contents of README.md"
+  assert_eq!(
+    doc.files[0].sha256,
+    "8ad92e709d09742c85ce0dd4fb1f60c33d493fff99ad67f9163ce00e0a5accd3"
+  );
+  assert_eq!(
+    doc.extracted_licenses["LicenseRef-Weird"].text,
+    "You may do weird things."
+  );
+  assert_eq!(doc.package.name, "proj");
+
+  Ok(())
+}
+
+/// Both formats carry the same information.
+#[test]
+fn formats() -> eyre::Result<()> {
+  let doc = document()?;
+
+  let mut tag_value = Vec::new();
+  doc.write(SbomFormat::TagValue, &mut tag_value)?;
+  let tag_value = String::from_utf8(tag_value)?;
+  assert!(tag_value.starts_with("SPDXVersion: SPDX-2.3\n"));
+  assert!(tag_value.contains("FileName: ./src/weird.c\n"));
+  assert!(
+    tag_value
+      .contains(&format!("FileChecksum: SHA256: {}\n", doc.files[1].sha256))
+  );
+  assert!(tag_value.contains("LicenseID: LicenseRef-Weird\n"));
+
+  let mut json = Vec::new();
+  doc.write(SbomFormat::Json, &mut json)?;
+  let json: serde_json::Value = serde_json::from_slice(&json)?;
+  assert_eq!(json["spdxVersion"], "SPDX-2.3");
+  assert_eq!(json["files"][1]["fileName"], "./src/weird.c");
+  assert_eq!(json["files"][1]["licenseConcluded"], "LicenseRef-Weird");
+  assert_eq!(
+    json["packages"][0]["packageVerificationCode"]["packageVerificationCodeValue"],
+    doc.package.verification_code.as_str()
+  );
+
+  Ok(())
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "serde")]
+
+use std::str::FromStr;
+
+use deb_strip_copyright::{
+  convert::{self, DataFormat},
+  deb822::{Deb822File, DuplicateFields, copyright::CopyrightFile},
+};
+
+const COPYRIGHT: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: proj
+Files-Excluded:
+ vendor/*
+ *.min.js
+
+Files: *
+Copyright: 2024 Someone
+ 2025 Someone Else
+License: MIT
+
+License: MIT
+ Permission is hereby granted...
+";
+
+/// Stanzas are maps in file order, and fields are newline-joined strings.
+#[test]
+fn deb822_json_shape() -> eyre::Result<()> {
+  let deb = Deb822File::from_str(COPYRIGHT)?;
+  let text = serde_json::to_string(&deb)?;
+  assert!(text.starts_with(
+    r#"{"stanzas":[{"Format":"https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/","Upstream-Name":"proj","Files-Excluded":"#
+  ));
+
+  let json: serde_json::Value = serde_json::from_str(&text)?;
+  assert_eq!(json["stanzas"][0]["Files-Excluded"], "\nvendor/*\n*.min.js");
+  assert_eq!(
+    json["stanzas"][2]["License"],
+    "MIT\nPermission is hereby granted..."
+  );
+
+  Ok(())
+}
+
+/// Both models survive a trip through both formats.
+#[test]
+fn round_trips() -> eyre::Result<()> {
+  for format in [DataFormat::Json, DataFormat::Toml] {
+    let data = convert::from_deb822(COPYRIGHT, format, false)?;
+    let back = convert::to_deb822(&data, format, false)?;
+    assert_eq!(back, COPYRIGHT, "{:?}", format);
+
+    let data = convert::from_deb822(COPYRIGHT, format, true)?;
+    let copyright: CopyrightFile = format.deserialize(&data)?;
+    assert_eq!(
+      copyright,
+      CopyrightFile::from_str(COPYRIGHT)?,
+      "{:?}",
+      format
+    );
+  }
+
+  Ok(())
+}
+
+/// Globs are checked on the way in.
+#[test]
+fn bad_glob() {
+  let json = r#"{
+    "header": {},
+    "files": [],
+    "licenses": [],
+    "files_excluded": ["foo*?"]
+  }"#;
+  assert!(DataFormat::Json.deserialize::<CopyrightFile>(json).is_err());
+}
+
+/// Duplicate fields can't be written as a map, so they are an error
+/// instead of output that can't be read back.
+#[test]
+fn duplicate_fields() -> eyre::Result<()> {
+  let file = Deb822File::parse(
+    "Files: *\nLicense: MIT\nlicense: BSD-3-clause\n",
+    DuplicateFields::Keep,
+  )?;
+  let err = serde_json::to_string(&file).unwrap_err();
+  assert!(err.to_string().contains("duplicate field \"license\""));
+  Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{path::PathBuf, process::Command, str::FromStr};
+
+use deb_strip_copyright::deb822::{
+  Deb822File, dsc::DscFile, signed::SignedFile,
+};
+
+const SIGNED_DSC: &str = "\
+-----BEGIN PGP SIGNED MESSAGE-----
+Hash: SHA512
+
+Format: 3.0 (quilt)
+Source: hello
+Version: 2.10-3
+- -Weird-Field: dash-escaped
+Files:
+ 6cd0ffea3884a4e79330338dcc2987d6 725946 hello_2.10.orig.tar.gz
+-----BEGIN PGP SIGNATURE-----
+
+iHUEARYIAB0WIQQW6w0XZWTjlYTHVgs6t3H3SisSzAUCatTTIQAKCRA6t3H3SisS
+=Wp9j
+-----END PGP SIGNATURE-----
+";
+
+#[test]
+fn strips_envelope() -> eyre::Result<()> {
+  let signed = SignedFile::from_str(SIGNED_DSC)?;
+  assert_eq!(signed.hashes, vec!["SHA512"]);
+  assert!(signed.body.starts_with("Format: 3.0 (quilt)\n"));
+  assert!(signed.body.contains("\n-Weird-Field: dash-escaped\n"));
+  assert!(
+    signed
+      .signature
+      .starts_with("-----BEGIN PGP SIGNATURE-----\n")
+  );
+  assert!(
+    signed
+      .signature
+      .ends_with("=Wp9j\n-----END PGP SIGNATURE-----")
+  );
+  assert_eq!(signed.deb822()?, Deb822File::from_str(SIGNED_DSC)?);
+
+  // The normal parsers strip it off by themselves
+  let dsc = DscFile::from_str(SIGNED_DSC)?;
+  assert_eq!(dsc.source, "hello");
+  assert_eq!(dsc.files[0].name, "hello_2.10.orig.tar.gz");
+  assert_eq!(
+    dsc
+      .extra
+      .get("-Weird-Field")
+      .unwrap()
+      .same_line_value
+      .as_deref(),
+    Some("dash-escaped")
+  );
+
+  Ok(())
+}
+
+#[test]
+fn errors() {
+  // Positions are in the signed file, not the body
+  let err = Deb822File::from_str(
+    "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\nA: 1\nnot a field\n\
+     -----BEGIN PGP SIGNATURE-----\n-----END PGP SIGNATURE-----\n",
+  )
+  .unwrap_err();
+  assert!(format!("{:#}", err).contains(" --> 5:1"), "{:#}", err);
+
+  for bad in [
+    "Source: hello\n",
+    "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n",
+    "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\nA: 1\n",
+    "-----BEGIN PGP SIGNED MESSAGE-----\n\nA: 1\n-B: 2\n\
+     -----BEGIN PGP SIGNATURE-----\n-----END PGP SIGNATURE-----\n",
+    "-----BEGIN PGP SIGNED MESSAGE-----\n\nA: 1\n\
+     -----BEGIN PGP SIGNATURE-----\nabc\n",
+  ] {
+    assert!(SignedFile::from_str(bad).is_err(), "{:?} parsed", bad);
+  }
+}
+
+/// Sign something with a throwaway key, and check it with `gpgv`.
+/// This is skipped if `gpg` isn't installed.
+#[test]
+fn verify() -> eyre::Result<()> {
+  let dir = std::env::temp_dir()
+    .join(format!("deb-strip-copyright-signed-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)?;
+  let gpg = |args: &[&str]| {
+    Command::new("gpg")
+      .env("GNUPGHOME", &dir)
+      .args(["--batch", "--quiet", "--passphrase", ""])
+      .args(args)
+      .output()
+  };
+  if gpg(&["--version"]).is_err() {
+    eprintln!("gpg is not installed, skipping");
+    return Ok(());
+  }
+
+  let message = dir.join("message");
+  let signed_path = dir.join("message.asc");
+  let keyring: PathBuf = dir.join("keyring.gpg");
+  std::fs::write(&message, "Source: hello\n-Dash: escaped\n")?;
+  gpg(&[
+    "--quick-gen-key",
+    "Test <test@example.com>",
+    "ed25519",
+    "sign",
+    "never",
+  ])?;
+  gpg(&[
+    "--clearsign",
+    "-o",
+    signed_path.to_str().unwrap(),
+    message.to_str().unwrap(),
+  ])?;
+  gpg(&["--export", "-o", keyring.to_str().unwrap()])?;
+
+  let source = std::fs::read_to_string(&signed_path)?;
+  let signed = SignedFile::from_str(&source)?;
+  assert_eq!(signed.body, "Source: hello\n-Dash: escaped\n");
+  let fingerprint = signed.verify(&keyring)?;
+  assert_eq!(fingerprint.len(), 40);
+
+  let tampered = SignedFile::from_str(&source.replace("hello", "hellx"))?;
+  assert!(tampered.verify(&keyring).is_err());
+
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
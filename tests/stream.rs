@@ -0,0 +1,109 @@
+use std::{
+  borrow::Cow,
+  io::{BufReader, Cursor},
+};
+
+use deb_strip_copyright::deb822::{
+  Deb822File, DuplicateFields, stream::StanzaReader,
+};
+
+const SOURCES: &str = "\
+Package: foo
+Binary: foo, libfoo1
+Version: 1.0-1
+# a comment in a stanza
+Files:
+ d41d8cd98f00b204e9800998ecf8427e 0 foo_1.0.orig.tar.xz
+ d41d8cd98f00b204e9800998ecf8427e 0 foo_1.0-1.debian.tar.xz
+
+Package: bar
+Version: 2.0
+\t
+Package: baz
+Description: short
+ long
+ .
+ more
+";
+
+/// A reader that hands out a few bytes at a time, so stanzas and lines
+/// are split across reads.
+fn reader(s: &str) -> BufReader<Cursor<Vec<u8>>> {
+  BufReader::with_capacity(7, Cursor::new(s.as_bytes().to_vec()))
+}
+
+#[test]
+fn agrees_with_whole_file() -> eyre::Result<()> {
+  let whole = Deb822File::parse(SOURCES, DuplicateFields::Error)?;
+  let streamed = StanzaReader::new(reader(SOURCES), DuplicateFields::Error)
+    .collect::<eyre::Result<Vec<_>>>()?;
+  assert_eq!(streamed, whole.stanzas());
+
+  // Spans are positions in the whole input, too
+  for (streamed, whole) in streamed.iter().zip(whole.stanzas()) {
+    assert_eq!(streamed.span(), whole.span());
+    for ((_, streamed), (_, whole)) in streamed.iter().zip(whole.iter()) {
+      assert_eq!(streamed.span, whole.span);
+    }
+  }
+  let baz = streamed[2].span().unwrap();
+  assert_eq!((baz.line, baz.column), (12, 1));
+  assert!(baz.text(SOURCES).starts_with("Package: baz\n"));
+
+  Ok(())
+}
+
+#[test]
+fn leading_blank_lines_and_comments() -> eyre::Result<()> {
+  let source = "\n\n# header comment\n\nA: 1\n\n\n# between\nB: 2";
+  let stanzas = StanzaReader::new(reader(source), DuplicateFields::Error)
+    .collect::<eyre::Result<Vec<_>>>()?;
+  assert_eq!(stanzas.len(), 2);
+  assert_eq!(stanzas[1].get("B").unwrap().span.unwrap().line, 9);
+  Ok(())
+}
+
+/// A broken stanza is an error, and reading carries on after it.
+#[test]
+fn errors() {
+  let source = "A: 1\n\nB: 2\nnot a field\n\nC: 3\n";
+  let results: Vec<_> =
+    StanzaReader::new(reader(source), DuplicateFields::Error).collect();
+  assert_eq!(results.len(), 3);
+  assert!(results[0].is_ok());
+  let err = results[1].as_ref().unwrap_err();
+  assert!(err.to_string().contains(" --> 4:1\n"), "{}", err);
+  assert!(err.to_string().contains("4 | not a field\n"), "{}", err);
+  assert!(results[2].is_ok());
+
+  // Text that isn't UTF-8 ends it
+  let bytes = b"A: 1\n\nB: \xff\n\nC: 3\n".to_vec();
+  let reader = BufReader::new(Cursor::new(bytes));
+  let results: Vec<_> =
+    StanzaReader::new(reader, DuplicateFields::Error).collect();
+  assert_eq!(results.len(), 2);
+  assert!(results[1].is_err());
+}
+
+/// Parsing a whole file, and reading a stanza without the iterator, don't
+/// copy the values.
+#[test]
+fn borrowed() -> eyre::Result<()> {
+  let whole = Deb822File::parse(SOURCES, DuplicateFields::Error)?;
+  let files = whole.stanzas()[0].get("Files").unwrap();
+  assert!(
+    files
+      .list_values
+      .iter()
+      .all(|line| matches!(line, Cow::Borrowed(_)))
+  );
+
+  let mut stanzas = StanzaReader::new(reader(SOURCES), DuplicateFields::Error);
+  let stanza = stanzas.read_stanza().unwrap()?;
+  let version = stanza.get("Version").unwrap();
+  assert!(matches!(
+    version.same_line_value,
+    Some(Cow::Borrowed("1.0-1"))
+  ));
+  Ok(())
+}
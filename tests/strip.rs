@@ -0,0 +1,215 @@
+use std::{
+  io::{Read, Write},
+  str::FromStr,
+};
+
+use deb_strip_copyright::{
+  deb822::copyright::CopyrightFile,
+  filter::{self, PathFilter},
+  strip::{Decision, EntryInfo, Stripper},
+};
+use eyre::bail;
+use flate2::{Compression, write::GzEncoder};
+use xz2::read::XzDecoder;
+
+mod common;
+use common::make_tar_xz;
+
+const COPYRIGHT: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded:
+ vendor/*
+ *.min.js
+";
+
+/// Return all the paths in a `.tar.xz`, and check their contents are intact.
+fn read_tar_xz(bytes: &[u8]) -> eyre::Result<Vec<String>> {
+  let mut archive = tar::Archive::new(XzDecoder::new(bytes));
+  let mut out = Vec::new();
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let path = entry.path()?.to_string_lossy().into_owned();
+    let mut data = String::new();
+    entry.read_to_string(&mut data)?;
+    let inner = path.strip_prefix("proj-1.0/").unwrap_or(&path);
+    if data != format!("contents of {}", inner) {
+      bail!("entry {:?} has wrong contents {:?}", &path, &data);
+    }
+    out.push(path);
+  }
+  Ok(out)
+}
+
+/// Strip from one buffer into another.
+#[test]
+fn strip_in_memory() -> eyre::Result<()> {
+  let input = make_tar_xz(&[
+    "README.md",
+    "vendor/foo/lib.rs",
+    "src/main.rs",
+    "web/jquery.min.js",
+  ])?;
+
+  let mut output = Vec::new();
+  let report = Stripper::new(CopyrightFile::from_str(COPYRIGHT)?)
+    .strip(input.as_slice(), &mut output)?;
+
+  assert_eq!(report.total_count, 4);
+  assert_eq!(report.keep_count, 2);
+  assert_eq!(report.excluded.len(), 2);
+  assert_eq!(
+    read_tar_xz(&output)?,
+    vec!["proj-1.0/README.md", "proj-1.0/src/main.rs"]
+  );
+
+  Ok(())
+}
+
+/// The callback sees every entry and can override the decision.
+#[test]
+fn callback_overrides() -> eyre::Result<()> {
+  let input = make_tar_xz(&["README.md", "vendor/foo/lib.rs", "src/main.rs"])?;
+
+  let mut seen = Vec::new();
+  let mut output = Vec::new();
+  let report = Stripper::new(CopyrightFile::from_str(COPYRIGHT)?)
+    .on_entry(|entry, decision| {
+      seen.push((entry.path.to_path_buf(), decision));
+      if entry.path.ends_with("README.md") {
+        Decision::Exclude
+      } else {
+        decision
+      }
+    })
+    .strip(input.as_slice(), &mut output)?;
+
+  assert_eq!(report.keep_count, 1);
+  assert_eq!(read_tar_xz(&output)?, vec!["proj-1.0/src/main.rs"]);
+  assert_eq!(
+    seen,
+    vec![
+      ("README.md".into(), Decision::Keep),
+      ("vendor/foo/lib.rs".into(), Decision::Exclude),
+      ("src/main.rs".into(), Decision::Keep),
+    ]
+  );
+
+  Ok(())
+}
+
+/// Filters see the whole entry, so they can drop files by size.
+#[test]
+fn filter_by_size() -> eyre::Result<()> {
+  let input = make_tar_xz(&[
+    "README.md",
+    "data/a_rather_large_blob.bin",
+    "src/main.rs",
+    "vendor/foo/lib.rs",
+  ])?;
+
+  // `contents of README.md` is 21 bytes, the blob is 40
+  let too_big = filter::from_fn(|entry: &EntryInfo| entry.size > 32);
+  let mut output = Vec::new();
+  let report = Stripper::new(CopyrightFile::from_str(COPYRIGHT)?.or(too_big))
+    .strip(input.as_slice(), &mut output)?;
+
+  assert_eq!(
+    report.excluded,
+    vec![
+      std::path::PathBuf::from("proj-1.0/data/a_rather_large_blob.bin"),
+      "proj-1.0/vendor/foo/lib.rs".into(),
+    ]
+  );
+  assert_eq!(
+    read_tar_xz(&output)?,
+    vec!["proj-1.0/README.md", "proj-1.0/src/main.rs"]
+  );
+
+  Ok(())
+}
+
+/// A dry run counts the same things as a real run.
+#[test]
+fn dry_run() -> eyre::Result<()> {
+  let input = make_tar_xz(&["README.md", "vendor/foo/lib.rs"])?;
+
+  let report = Stripper::new(CopyrightFile::from_str(COPYRIGHT)?)
+    .dry_run(input.as_slice())?;
+  assert_eq!(report.total_count, 2);
+  assert_eq!(report.keep_count, 1);
+
+  Ok(())
+}
+
+/// Gzipped and uncompressed input tarballs work too. The output is
+/// always xz.
+#[test]
+fn other_compressions() -> eyre::Result<()> {
+  let input = make_tar_xz(&["README.md", "vendor/foo/lib.rs"])?;
+  let mut tar = Vec::new();
+  XzDecoder::new(input.as_slice()).read_to_end(&mut tar)?;
+  let mut gz = GzEncoder::new(Vec::new(), Compression::fast());
+  gz.write_all(&tar)?;
+  let gz = gz.finish()?;
+
+  for input in [gz, tar] {
+    let mut output = Vec::new();
+    Stripper::new(CopyrightFile::from_str(COPYRIGHT)?)
+      .strip(input.as_slice(), &mut output)?;
+    assert_eq!(read_tar_xz(&output)?, vec!["proj-1.0/README.md"]);
+  }
+
+  Ok(())
+}
+
+/// Paths that aren't UTF-8 are checked and written out byte for byte.
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths() -> eyre::Result<()> {
+  use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::PathBuf};
+
+  let paths: &[&[u8]] = &[
+    b"proj-1.0/docs/caf\xe9",
+    b"proj-1.0/data/\xff.bin",
+    b"proj-1.0/README",
+  ];
+  let mut builder = tar::Builder::new(Vec::new());
+  for path in paths {
+    let mut header = tar::Header::new_gnu();
+    header.set_mode(0o644);
+    header.set_size(0);
+    header.set_cksum();
+    builder.append_data(&mut header, OsStr::from_bytes(path), &[][..])?;
+  }
+  let input = builder.into_inner()?;
+
+  // A lossy conversion would turn `caf\xe9` into `caf\u{fffd}` and
+  // exclude it
+  let copyright = CopyrightFile::from_str(
+    "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded: docs/caf\u{fffd} data/*.bin
+",
+  )?;
+  let mut output = Vec::new();
+  let report = Stripper::new(copyright).strip(input.as_slice(), &mut output)?;
+  assert_eq!(
+    report.excluded,
+    vec![PathBuf::from(OsStr::from_bytes(b"proj-1.0/data/\xff.bin"))]
+  );
+
+  let mut archive = tar::Archive::new(XzDecoder::new(output.as_slice()));
+  let mut out_paths = Vec::new();
+  for entry in archive.entries()? {
+    out_paths.push(entry?.path_bytes().into_owned());
+  }
+  assert_eq!(
+    out_paths,
+    vec![
+      b"proj-1.0/docs/caf\xe9".to_vec(),
+      b"proj-1.0/README".to_vec()
+    ]
+  );
+
+  Ok(())
+}
@@ -0,0 +1,212 @@
+use std::{io::Write, path::Path, str::FromStr};
+
+use deb_strip_copyright::{
+  deb822::{DuplicateFields, stream::StanzaReader},
+  glob::Glob,
+  survey::{self, SurveyHit},
+};
+use flate2::{Compression, write::GzEncoder};
+use xz2::write::XzEncoder;
+
+const EXCLUDING: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Files-Excluded: vendor/* *.min.js
+";
+
+const NOT_EXCLUDING: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: bar
+";
+
+const FREE_FORM: &str = "\
+This package was debianized by someone.
+
+It is licensed under the GPL.
+";
+
+fn xz(data: &[u8]) -> eyre::Result<Vec<u8>> {
+  let mut encoder = XzEncoder::new(Vec::new(), 1);
+  encoder.write_all(data)?;
+  Ok(encoder.finish()?)
+}
+
+fn gz(data: &[u8]) -> eyre::Result<Vec<u8>> {
+  let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+  encoder.write_all(data)?;
+  Ok(encoder.finish()?)
+}
+
+/// A plain tar with one file at `path`.
+fn tar_with(path: &str, contents: &str) -> eyre::Result<Vec<u8>> {
+  let mut builder = tar::Builder::new(Vec::new());
+  let mut header = tar::Header::new_gnu();
+  header.set_mode(0o644);
+  header.set_size(contents.len() as u64);
+  header.set_cksum();
+  builder.append_data(&mut header, path, contents.as_bytes())?;
+  Ok(builder.into_inner()?)
+}
+
+fn sources_stanza(package: &str, version: &str, files: &[&str]) -> String {
+  let mut stanza = format!(
+    "Package: {}\nVersion: {}\nDirectory: pool/main/{}\nChecksums-Sha256:\n",
+    package, version, package
+  );
+  for file in files {
+    stanza.push_str(&format!(" 00 0 {}\n", file));
+  }
+  stanza
+}
+
+fn write(path: &Path, data: &[u8]) -> eyre::Result<()> {
+  std::fs::create_dir_all(path.parent().unwrap())?;
+  std::fs::write(path, data)?;
+  Ok(())
+}
+
+/// Lay out a small mirror with two components, one with an xz index and
+/// one with a gzip one.
+fn make_mirror(root: &Path) -> eyre::Result<()> {
+  let _ = std::fs::remove_dir_all(root);
+  let pool = root.join("pool/main");
+
+  write(
+    &pool.join("foo/foo_1.0-1.debian.tar.xz"),
+    &xz(&tar_with("debian/copyright", EXCLUDING)?)?,
+  )?;
+  write(
+    &pool.join("bar/bar_2.0-1.debian.tar.gz"),
+    &gz(&tar_with("debian/copyright", NOT_EXCLUDING)?)?,
+  )?;
+  // Native, so the copyright file is one directory down
+  write(
+    &pool.join("baz/baz_3.tar.xz"),
+    &xz(&tar_with("baz-3/debian/copyright", EXCLUDING)?)?,
+  )?;
+  write(
+    &pool.join("old/old_1.0-1.debian.tar.xz"),
+    &xz(&tar_with("debian/copyright", FREE_FORM)?)?,
+  )?;
+
+  let main = [
+    sources_stanza(
+      "foo",
+      "1.0-1",
+      &[
+        "foo_1.0-1.dsc",
+        "foo_1.0.orig.tar.xz",
+        "foo_1.0-1.debian.tar.xz",
+      ],
+    ),
+    sources_stanza(
+      "bar",
+      "2.0-1",
+      &["bar_2.0.orig.tar.gz", "bar_2.0-1.debian.tar.gz"],
+    ),
+    sources_stanza(
+      "ancient",
+      "1.0-1",
+      &["ancient_1.0.orig.tar.gz", "ancient_1.0-1.diff.gz"],
+    ),
+  ]
+  .join("\n");
+  write(
+    &root.join("dists/unstable/main/source/Sources.xz"),
+    &xz(main.as_bytes())?,
+  )?;
+  let contrib = [
+    sources_stanza("baz", "3", &["baz_3.dsc", "baz_3.tar.xz"]),
+    sources_stanza(
+      "old",
+      "1.0-1",
+      &["old_1.0.orig.tar.xz", "old_1.0-1.debian.tar.xz"],
+    ),
+  ]
+  .join("\n");
+  write(
+    &root.join("dists/unstable/contrib/source/Sources.gz"),
+    &gz(contrib.as_bytes())?,
+  )?;
+  Ok(())
+}
+
+#[test]
+fn finds_files_excluded() -> eyre::Result<()> {
+  let root = std::env::temp_dir()
+    .join(format!("deb-strip-copyright-survey-{}", std::process::id()));
+  make_mirror(&root)?;
+
+  let indices = survey::source_indices(&root, "unstable")?;
+  assert_eq!(
+    indices,
+    vec![
+      root.join("dists/unstable/contrib/source/Sources.gz"),
+      root.join("dists/unstable/main/source/Sources.xz"),
+    ]
+  );
+
+  let mut hits = Vec::new();
+  for index in &indices {
+    for stanza in StanzaReader::open(index, DuplicateFields::Error)? {
+      if let Some(hit) = survey::survey_package(&root, &stanza?)? {
+        hits.push(hit);
+      }
+    }
+  }
+  let excludes = vec![Glob::from_str("vendor/*")?, Glob::from_str("*.min.js")?];
+  assert_eq!(
+    hits,
+    vec![
+      SurveyHit {
+        package: "baz".to_owned(),
+        version: "3".to_owned(),
+        excludes: excludes.clone(),
+      },
+      SurveyHit {
+        package: "foo".to_owned(),
+        version: "1.0-1".to_owned(),
+        excludes,
+      },
+    ]
+  );
+  assert_eq!(hits[1].to_string(), "foo\t1.0-1\tvendor/* *.min.js");
+
+  assert!(survey::source_indices(&root, "stable").is_err());
+  std::fs::remove_dir_all(&root)?;
+  Ok(())
+}
+
+/// The same index reads the same whatever it's compressed with.
+#[test]
+fn compressed_indices() -> eyre::Result<()> {
+  let dir = std::env::temp_dir().join(format!(
+    "deb-strip-copyright-indices-{}",
+    std::process::id()
+  ));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)?;
+
+  let index = format!(
+    "{}\n{}",
+    sources_stanza("foo", "1.0-1", &["foo_1.0-1.dsc"]),
+    sources_stanza("bar", "2.0-1", &["bar_2.0-1.dsc"])
+  );
+  // Named wrongly on purpose: the format comes from the contents
+  write(&dir.join("Sources"), index.as_bytes())?;
+  write(&dir.join("Sources.xz"), &gz(index.as_bytes())?)?;
+  write(&dir.join("Sources.gz"), &xz(index.as_bytes())?)?;
+
+  let mut read = Vec::new();
+  for name in ["Sources", "Sources.xz", "Sources.gz"] {
+    let stanzas = StanzaReader::open(dir.join(name), DuplicateFields::Error)?
+      .collect::<eyre::Result<Vec<_>>>()?;
+    assert_eq!(stanzas.len(), 2);
+    read.push(stanzas);
+  }
+  assert_eq!(read[0], read[1]);
+  assert_eq!(read[0], read[2]);
+
+  assert!(StanzaReader::open(dir.join("missing"), Default::default()).is_err());
+  std::fs::remove_dir_all(&dir)?;
+  Ok(())
+}
@@ -0,0 +1,127 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use deb_strip_copyright::version::Version;
+
+/// `(a, b, ordering of a to b)`, from dpkg's own version comparison
+/// tests (`t/Dpkg_Version.t`), checked against `dpkg --compare-versions`.
+const DPKG_VECTORS: &[(&str, &str, i32)] = &[
+  ("1.0-1", "2.0-2", -1),
+  ("2.2~rc-4", "2.2-1", -1),
+  ("2.2-1", "2.2~rc-4", 1),
+  ("1.0000-1", "1.0-1", 0),
+  ("1", "0:1", 0),
+  ("0", "0:0-0", 0),
+  ("2:2.5", "1:7.5", 1),
+  ("1:0foo", "0foo", 1),
+  ("0:0foo", "0foo", 0),
+  ("0foo", "0foo", 0),
+  ("0foo-0", "0foo", 0),
+  ("0foo", "0foo-0", 0),
+  ("0foo", "0fo", 1),
+  ("0foo-0", "0foo+", -1),
+  ("0foo~1", "0foo", -1),
+  ("0foo~foo+Bar", "0foo~foo+bar", -1),
+  ("0foo~~", "0foo~", -1),
+  ("1~", "1", -1),
+  (
+    "12345+that-really-is-some-ver-0",
+    "12345+that-really-is-some-ver-10",
+    -1,
+  ),
+  ("0foo-0", "0foo-01", -1),
+  ("0foo.bar", "0foobar", 1),
+  ("0foo.bar", "0foo1bar", 1),
+  ("0foo.bar", "0foo0bar", 1),
+  ("0foo1bar-1", "0foobar-1", -1),
+  ("0foo2.0", "0foo2", 1),
+  ("0foo2.0.0", "0foo2.10.0", -1),
+  ("0foo2.0", "0foo2.0.0", -1),
+  ("0foo2.0", "0foo2.10", -1),
+  ("0foo2.1", "0foo2.10", -1),
+  ("1.09", "1.9", 0),
+  ("1.0.8+nmu1", "1.0.8", 1),
+  ("3.11", "3.10+nmu1", 1),
+  ("0.9j-20080306-4", "0.9i-20070324-2", 1),
+  ("1.2.0~b7-1", "1.2.0~b6-1", 1),
+  ("1.011-1", "1.06-2", 1),
+  ("0.0.9+dfsg1-1", "0.0.8+dfsg1-3", 1),
+  ("4.6.99+svn6582-1", "4.6.99+svn6496-1", 1),
+  ("53", "52", 1),
+  ("0.9.9~pre122-1", "0.9.9~pre111-1", 1),
+  ("2:2.3.2-2+lenny2", "2:2.3.2-2", 1),
+  ("1:3.8.1-1", "3.8.GA-1", 1),
+  ("1.0.1+gpl-1", "1.0.1-2", 1),
+  ("1a", "1000a", -1),
+];
+
+#[test]
+fn dpkg_ordering() -> eyre::Result<()> {
+  for &(a, b, expected) in DPKG_VECTORS {
+    let expected = expected.cmp(&0);
+    let (a_version, b_version) = (Version::from_str(a)?, Version::from_str(b)?);
+    assert_eq!(a_version.cmp(&b_version), expected, "{} vs {}", a, b);
+    assert_eq!(
+      b_version.cmp(&a_version),
+      expected.reverse(),
+      "{} vs {}",
+      b,
+      a
+    );
+  }
+
+  Ok(())
+}
+
+#[test]
+fn parts() -> eyre::Result<()> {
+  let version = Version::from_str("1:2.30+dfsg-1-2")?;
+  assert_eq!(version.epoch, 1);
+  assert_eq!(version.upstream, "2.30+dfsg-1");
+  assert_eq!(version.revision.as_deref(), Some("2"));
+  assert_eq!(version.to_string(), "1:2.30+dfsg-1-2");
+
+  let native = Version::from_str("0:3.4")?;
+  assert_eq!(native.revision, None);
+  assert_eq!(native.to_string(), "3.4");
+  assert_eq!(native.cmp(&Version::from_str("3.4-0")?), Ordering::Equal);
+
+  for bad in [
+    "", "a1.0", "1.0-", ":1.0", "x:1.0", "1.0 2", "1.0_1", "1:-1",
+  ] {
+    assert!(Version::from_str(bad).is_err(), "{:?} parsed", bad);
+  }
+
+  Ok(())
+}
+
+#[test]
+fn repack_suffix() -> eyre::Result<()> {
+  let version = Version::from_str("1:2.30+dfsg1-3")?;
+  assert_eq!(version.repack_suffix(), Some("+dfsg1"));
+  assert_eq!(version.without_repack_suffix().to_string(), "1:2.30-3");
+  assert_eq!(version.with_repack_suffix("+ds").to_string(), "1:2.30+ds-3");
+
+  let version = Version::from_str("4.0+ds.2+really4.0")?;
+  assert_eq!(version.repack_suffix(), Some("+ds.2+really4.0"));
+
+  // Not a repack suffix
+  let version = Version::from_str("1.0+dsp-1")?;
+  assert_eq!(version.repack_suffix(), None);
+  assert_eq!(
+    version.with_repack_suffix("+dfsg").to_string(),
+    "1.0+dsp+dfsg-1"
+  );
+
+  Ok(())
+}
+
+#[test]
+fn orig_tarball_name() -> eyre::Result<()> {
+  let version = Version::from_str("1:2.30-1")?.with_repack_suffix("+dfsg");
+  assert_eq!(
+    version.orig_tarball_name("binutils", "xz"),
+    "binutils_2.30+dfsg.orig.tar.xz"
+  );
+
+  Ok(())
+}